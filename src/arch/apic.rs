@@ -0,0 +1,27 @@
+//! Discovery of the local APIC. Mapping and programming it is left to higher-level code.
+
+use super::cpu::{self, Feature};
+
+/// The MSR that holds the physical base address of the local APIC and its global enable flag.
+const IA32_APIC_BASE: u32 = 0x1b;
+
+/// Flag in [`IA32_APIC_BASE`] that is set on the bootstrap processor.
+const BSP_FLAG: u64 = 1 << 8;
+
+/// Mask of the base address bits in [`IA32_APIC_BASE`]. The base is aligned to 4 KiB.
+const BASE_MASK: u64 = !0xfff;
+
+/// Returns the physical base address of the local APIC's registers, or `None` if the CPU doesn't
+/// have a local APIC. Addresses that don't fit into `usize` are truncated, but firmware keeps the
+/// APIC at its default address of `0xfee0_0000` in practice.
+#[allow(dead_code)]
+pub fn local_apic_base() -> Option<usize> {
+    cpu::has_feature(Feature::Apic).then(|| (super::read_msr(IA32_APIC_BASE) & BASE_MASK) as usize)
+}
+
+/// Returns whether the current core is the bootstrap processor, i.e. the one that started executing
+/// the firmware. CPUs without a local APIC only have a single core, which is the bootstrap one.
+#[allow(dead_code)]
+pub fn is_bsp() -> bool {
+    !cpu::has_feature(Feature::Apic) || super::read_msr(IA32_APIC_BASE) & BSP_FLAG != 0
+}
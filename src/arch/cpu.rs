@@ -0,0 +1,141 @@
+//! Identification of the CPU and its features using the `cpuid` instruction.
+
+use core::arch::asm;
+use core::fmt::{Display, Formatter, Result};
+
+/// The registers returned by the `cpuid` instruction.
+#[derive(Debug, Clone, Copy)]
+pub struct CpuidResult {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+/// CPU features the kernel cares about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feature {
+    /// Page size extension (4 MiB pages without PAE).
+    Pse,
+
+    /// Physical address extension (64-bit page table entries).
+    Pae,
+
+    /// On-chip local APIC.
+    Apic,
+
+    /// Global pages that are not flushed from the TLB on CR3 reloads.
+    Pge,
+
+    /// No-execute bit in page table entries (requires PAE).
+    Nx,
+}
+
+impl Feature {
+    /// All features that can be queried using [`has_feature()`].
+    pub const ALL: [Feature; 5] = [
+        Feature::Pse,
+        Feature::Pae,
+        Feature::Apic,
+        Feature::Pge,
+        Feature::Nx,
+    ];
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(match self {
+            Feature::Pse => "pse",
+            Feature::Pae => "pae",
+            Feature::Apic => "apic",
+            Feature::Pge => "pge",
+            Feature::Nx => "nx",
+        })
+    }
+}
+
+/// Executes `cpuid` for the given leaf (and sub-leaf 0). Returns `None` if the CPU doesn't support
+/// the `cpuid` instruction or the leaf. Leaves from `0x8000_0000` upwards are extended leaves.
+pub fn cpuid(leaf: u32) -> Option<CpuidResult> {
+    if !is_cpuid_supported() {
+        return None;
+    }
+
+    // Leaf 0 and 0x8000_0000 return the highest supported (extended) leaf in eax
+    let highest_leaf = cpuid_unchecked(leaf & 0x8000_0000).eax;
+    (leaf <= highest_leaf).then(|| cpuid_unchecked(leaf))
+}
+
+/// Returns whether the CPU supports the given feature. CPUs that predate the `cpuid` instruction
+/// are assumed to support none of them.
+pub fn has_feature(feature: Feature) -> bool {
+    // All of these features are reported in edx
+    let (leaf, bit) = match feature {
+        Feature::Pse => (0x0000_0001, 3),
+        Feature::Pae => (0x0000_0001, 6),
+        Feature::Apic => (0x0000_0001, 9),
+        Feature::Pge => (0x0000_0001, 13),
+        Feature::Nx => (0x8000_0001, 20),
+    };
+
+    cpuid(leaf).is_some_and(|result| result.edx & (1 << bit) != 0)
+}
+
+/// Returns the 12-character vendor string of the CPU (e.g. `GenuineIntel`) if it supports the
+/// `cpuid` instruction.
+pub fn vendor() -> Option<[u8; 12]> {
+    let result = cpuid(0)?;
+
+    let mut vendor = [0; 12];
+    vendor[0..4].copy_from_slice(&result.ebx.to_le_bytes());
+    vendor[4..8].copy_from_slice(&result.edx.to_le_bytes());
+    vendor[8..12].copy_from_slice(&result.ecx.to_le_bytes());
+    Some(vendor)
+}
+
+/// The `cpuid` instruction is supported if the ID bit (21) of the EFLAGS register can be toggled.
+/// Some i486 and older CPUs don't support it.
+fn is_cpuid_supported() -> bool {
+    const ID_FLAG: usize = 1 << 21;
+
+    let original: usize;
+    let toggled: usize;
+    unsafe {
+        asm!(
+            "pushfl",
+            "pop {original}",
+            "mov {original}, {toggled}",
+            "xor ${id_flag}, {toggled}",
+            "push {toggled}",
+            "popfl",
+            "pushfl",
+            "pop {toggled}",
+            "push {original}",
+            "popfl",
+            original = out(reg) original,
+            toggled = out(reg) toggled,
+            id_flag = const ID_FLAG,
+            options(att_syntax)
+        );
+    }
+    (original ^ toggled) & ID_FLAG != 0
+}
+
+fn cpuid_unchecked(leaf: u32) -> CpuidResult {
+    let (eax, ebx, ecx, edx);
+
+    // LLVM reserves ebx, so its value is swapped into another register around the instruction
+    unsafe {
+        asm!(
+            "mov %ebx, {ebx:e}",
+            "cpuid",
+            "xchg %ebx, {ebx:e}",
+            ebx = out(reg) ebx,
+            inout("eax") leaf => eax,
+            inout("ecx") 0 => ecx,
+            out("edx") edx,
+            options(att_syntax, nomem, nostack, preserves_flags)
+        );
+    }
+    CpuidResult { eax, ebx, ecx, edx }
+}
@@ -0,0 +1,122 @@
+//! The global descriptor table (GDT) defines the memory segments of the x86 architecture. As the
+//! kernel relies on paging instead of segmentation, it only uses flat segments that span the entire
+//! 4 GiB address space. The bootloader leaves behind a GDT of its own, but there is no guarantee
+//! where it is located or that it stays intact, so the kernel installs its own as early as
+//! possible.
+
+use core::arch::asm;
+
+/// Segment selector of the ring 0 code segment.
+pub const KERNEL_CODE_SELECTOR: u16 = 0x08;
+
+/// Segment selector of the ring 0 data segment.
+pub const KERNEL_DATA_SELECTOR: u16 = 0x10;
+
+/// Segment selector of the task state segment, if one has been added using [`Gdt::with_tss()`].
+pub const TSS_SELECTOR: u16 = 0x18;
+
+/// A single 8-byte segment descriptor.
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct Descriptor(u64);
+
+impl Descriptor {
+    /// The first entry of the GDT must be a null descriptor. Loading a segment register with a null
+    /// selector is allowed, but using it raises a general protection fault.
+    pub const NULL: Self = Self(0);
+
+    /// Present, ring 0, code segment, executable and readable.
+    const KERNEL_CODE_ACCESS: u8 = 0b1001_1010;
+
+    /// Present, ring 0, data segment, writeable.
+    const KERNEL_DATA_ACCESS: u8 = 0b1001_0010;
+
+    /// Present, ring 0, available 32-bit TSS.
+    const TSS_ACCESS: u8 = 0b1000_1001;
+
+    /// 4 KiB granularity and 32-bit default operand size.
+    const FLAT_FLAGS: u8 = 0b1100;
+
+    /// Constructs a segment descriptor. The 32-bit base address and 20-bit limit are scattered
+    /// across the descriptor for historical reasons.
+    pub const fn new(base: u32, limit: u32, access: u8, flags: u8) -> Self {
+        let base = base as u64;
+        let limit = limit as u64;
+        Self(
+            (limit & 0xffff)
+                | (base & 0xff_ffff) << 16
+                | (access as u64) << 40
+                | (limit >> 16 & 0xf) << 48
+                | (flags as u64 & 0xf) << 52
+                | (base >> 24 & 0xff) << 56,
+        )
+    }
+
+    /// Constructs a descriptor for a segment that spans the entire 4 GiB address space.
+    const fn flat(access: u8) -> Self {
+        Self::new(0, 0xf_ffff, access, Self::FLAT_FLAGS)
+    }
+}
+
+/// The global descriptor table of the kernel. It contains the null descriptor, the ring 0 code and
+/// data segments and a slot for a task state segment, which is required for handling double faults
+/// on a known-good stack and for entering user mode later on.
+#[repr(C, align(8))]
+pub struct Gdt {
+    descriptors: [Descriptor; 4],
+}
+
+impl Gdt {
+    /// Constructs a GDT with flat ring 0 code and data segments and an empty TSS slot.
+    pub const fn new() -> Self {
+        Self {
+            descriptors: [
+                Descriptor::NULL,
+                Descriptor::flat(Descriptor::KERNEL_CODE_ACCESS),
+                Descriptor::flat(Descriptor::KERNEL_DATA_ACCESS),
+                Descriptor::NULL,
+            ],
+        }
+    }
+
+    /// Adds a descriptor for the task state segment at the given address to the TSS slot.
+    #[allow(dead_code)]
+    pub const fn with_tss(mut self, base: u32, limit: u32) -> Self {
+        self.descriptors[(TSS_SELECTOR / 8) as usize] =
+            Descriptor::new(base, limit, Descriptor::TSS_ACCESS, 0);
+        self
+    }
+
+    /// Loads this GDT into the GDTR register and reloads all segment registers so that they refer
+    /// to the flat kernel segments. The code segment register can only be reloaded by a far jump.
+    pub fn install(&'static self) {
+        /// The operand of the `lgdt` instruction.
+        #[repr(C, packed)]
+        struct GdtPointer {
+            limit: u16,
+            base: *const Gdt,
+        }
+
+        let pointer = GdtPointer {
+            limit: (core::mem::size_of::<Self>() - 1) as u16,
+            base: self,
+        };
+
+        unsafe {
+            asm!(
+                "lgdt ({pointer})",
+                "ljmp ${code}, $2f",
+                "2:",
+                "mov {data:x}, %ds",
+                "mov {data:x}, %es",
+                "mov {data:x}, %fs",
+                "mov {data:x}, %gs",
+                "mov {data:x}, %ss",
+                pointer = in(reg) &pointer,
+                code = const KERNEL_CODE_SELECTOR,
+                data = in(reg) u32::from(KERNEL_DATA_SELECTOR),
+                options(att_syntax, nostack, preserves_flags)
+            );
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! The interrupt descriptor table (IDT) tells the CPU where to continue when an interrupt or an
+//! exception occurs. Every one of the 256 vectors enters the kernel through a small assembly stub
+//! that saves the interrupted context in an [`InterruptFrame`] and calls a common dispatcher. The
+//! dispatcher then invokes the handler that has been registered for the vector using [`register()`]
+//! or a default handler that reports the interrupt.
+
+use core::arch::{asm, global_asm};
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of vectors supported by the CPU.
+const VECTOR_COUNT: usize = 256;
+
+/// Vectors below this number are reserved for CPU exceptions.
+const EXCEPTION_COUNT: usize = 32;
+
+/// Vector of the page fault exception.
+pub const PAGE_FAULT: u8 = 14;
+
+/// Each entry stub is aligned to this many bytes, so the address of a stub can be computed from its
+/// vector number.
+const STUB_SIZE: usize = 16;
+
+/// The state of the interrupted code as saved by the entry stub. The general purpose registers are
+/// pushed by `pushal`, the vector and error code by the stub itself and the rest by the CPU.
+#[derive(Debug)]
+#[repr(C)]
+pub struct InterruptFrame {
+    pub edi: u32,
+    pub esi: u32,
+    pub ebp: u32,
+    /// Value of `esp` after the CPU pushed its part of the frame, which makes it rather useless.
+    _esp: u32,
+    pub ebx: u32,
+    pub edx: u32,
+    pub ecx: u32,
+    pub eax: u32,
+
+    /// The vector number of the interrupt.
+    pub vector: u32,
+
+    /// The error code pushed by the CPU for some exceptions, zero for all other vectors.
+    pub error_code: u32,
+
+    pub eip: u32,
+    pub cs: u32,
+    pub eflags: u32,
+}
+
+/// Function that handles the interrupt of a certain vector. Once it returns, the interrupted code
+/// continues with the (possibly modified) state of the frame.
+pub type Handler = fn(&mut InterruptFrame);
+
+/// The kernel's interrupt descriptor table.
+static IDT: Idt = Idt::new();
+
+/// Handlers registered for each vector as function pointers, or zero if there is none.
+static HANDLERS: [AtomicUsize; VECTOR_COUNT] = [const { AtomicUsize::new(0) }; VECTOR_COUNT];
+
+/// An interrupt gate descriptor.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+struct Gate(u64);
+
+impl Gate {
+    /// Present, ring 0, 32-bit interrupt gate. Interrupt gates disable interrupts on entry.
+    const INTERRUPT_GATE: u8 = 0b1000_1110;
+
+    const fn missing() -> Self {
+        Self(0)
+    }
+
+    fn new(handler: usize) -> Self {
+        use super::gdt::KERNEL_CODE_SELECTOR;
+
+        let handler = handler as u64;
+        Self(
+            (handler & 0xffff)
+                | u64::from(KERNEL_CODE_SELECTOR) << 16
+                | u64::from(Self::INTERRUPT_GATE) << 40
+                | (handler >> 16 & 0xffff) << 48,
+        )
+    }
+}
+
+#[repr(C, align(8))]
+struct Idt {
+    gates: UnsafeCell<[Gate; VECTOR_COUNT]>,
+}
+
+// SAFETY: The gates are only written by `install()`, which runs once during boot.
+unsafe impl Sync for Idt {}
+
+impl Idt {
+    const fn new() -> Self {
+        Self {
+            gates: UnsafeCell::new([Gate::missing(); VECTOR_COUNT]),
+        }
+    }
+}
+
+/// Fills the IDT with gates for all entry stubs and loads it into the IDTR register. Page faults
+/// are handled by a handler that reports the faulting address. This must be called only once and
+/// before interrupts are enabled.
+pub fn install() {
+    /// The operand of the `lidt` instruction.
+    #[repr(C, packed)]
+    struct IdtPointer {
+        limit: u16,
+        base: *const Idt,
+    }
+
+    extern "C" {
+        /// Start of the entry stubs defined below.
+        static interrupt_stubs: u8;
+    }
+
+    let stubs = core::ptr::addr_of!(interrupt_stubs) as usize;
+
+    // SAFETY: Interrupts are disabled and the IDT isn't loaded yet, so nothing else accesses it.
+    let gates = unsafe { &mut *IDT.gates.get() };
+    for (vector, gate) in gates.iter_mut().enumerate() {
+        *gate = Gate::new(stubs + vector * STUB_SIZE);
+    }
+
+    register(PAGE_FAULT, handle_page_fault);
+
+    let pointer = IdtPointer {
+        limit: (core::mem::size_of::<Idt>() - 1) as u16,
+        base: &IDT,
+    };
+    unsafe {
+        asm!(
+            "lidt ({})",
+            in(reg) &pointer,
+            options(att_syntax, nostack, preserves_flags)
+        );
+    }
+}
+
+/// Registers the handler for the given vector, replacing the previous one.
+pub fn register(vector: u8, handler: Handler) {
+    HANDLERS[vector as usize].store(handler as usize, Ordering::Release);
+}
+
+/// Called by the entry stubs with a pointer to the frame they have built on the stack.
+#[no_mangle]
+extern "C" fn dispatch_interrupt(frame: &mut InterruptFrame) {
+    match HANDLERS[frame.vector as usize].load(Ordering::Acquire) {
+        0 => handle_unregistered(frame),
+        handler => {
+            // SAFETY: Only valid handlers are stored by `register()`.
+            let handler: Handler = unsafe { core::mem::transmute(handler) };
+            handler(frame);
+        }
+    }
+}
+
+/// Reports exceptions that have no handler and halts, as the interrupted code cannot continue.
+/// Other interrupts are reported and ignored.
+fn handle_unregistered(frame: &mut InterruptFrame) {
+    if (frame.vector as usize) < EXCEPTION_COUNT {
+        log::error!(
+            "Unhandled exception {} ({}), error code 0x{:x}",
+            frame.vector,
+            exception_name(frame.vector),
+            frame.error_code
+        );
+        log_frame(frame);
+        super::halt_core();
+    } else {
+        log::warn!("Unhandled interrupt {}", frame.vector);
+    }
+}
+
+fn handle_page_fault(frame: &mut InterruptFrame) {
+    const PRESENT: u32 = 1 << 0;
+    const WRITE: u32 = 1 << 1;
+    const USER: u32 = 1 << 2;
+
+    log::error!(
+        "Page fault @ 0x{:x}: {} {} in {} mode",
+        super::read_cr2(),
+        if frame.error_code & PRESENT != 0 {
+            "protection violation on"
+        } else {
+            "non-present page on"
+        },
+        if frame.error_code & WRITE != 0 {
+            "write"
+        } else {
+            "read"
+        },
+        if frame.error_code & USER != 0 {
+            "user"
+        } else {
+            "kernel"
+        }
+    );
+    log_frame(frame);
+    super::halt_core();
+}
+
+fn log_frame(frame: &InterruptFrame) {
+    log::error!(
+        "eip=0x{:08x} cs=0x{:04x} eflags=0x{:08x}",
+        frame.eip,
+        frame.cs,
+        frame.eflags
+    );
+    log::error!(
+        "eax=0x{:08x} ebx=0x{:08x} ecx=0x{:08x} edx=0x{:08x}",
+        frame.eax,
+        frame.ebx,
+        frame.ecx,
+        frame.edx
+    );
+    log::error!(
+        "esi=0x{:08x} edi=0x{:08x} ebp=0x{:08x}",
+        frame.esi,
+        frame.edi,
+        frame.ebp
+    );
+}
+
+fn exception_name(vector: u32) -> &'static str {
+    match vector {
+        0 => "divide error",
+        1 => "debug",
+        2 => "non-maskable interrupt",
+        3 => "breakpoint",
+        4 => "overflow",
+        5 => "bound range exceeded",
+        6 => "invalid opcode",
+        7 => "device not available",
+        8 => "double fault",
+        10 => "invalid TSS",
+        11 => "segment not present",
+        12 => "stack-segment fault",
+        13 => "general protection fault",
+        14 => "page fault",
+        16 => "x87 floating-point exception",
+        17 => "alignment check",
+        18 => "machine check",
+        19 => "SIMD floating-point exception",
+        20 => "virtualization exception",
+        21 => "control protection exception",
+        28 => "hypervisor injection exception",
+        29 => "VMM communication exception",
+        30 => "security exception",
+        _ => "reserved",
+    }
+}
+
+// One entry stub per vector, each aligned to `STUB_SIZE` bytes. The CPU only pushes an error code
+// for some exceptions, so the other stubs push a zero in its place to keep the frame layout the
+// same. Interrupt gates clear the interrupt flag, which is restored by `iret`.
+global_asm!(
+    ".section .text",
+    ".align {stub_size}",
+    ".global interrupt_stubs",
+    "interrupt_stubs:",
+    ".set vector, 0",
+    ".rept {vector_count}",
+    "    .align {stub_size}",
+    "    .set error_code, vector == 8 || (vector >= 10 && vector <= 14) || vector == 17",
+    "    .set error_code, error_code || vector == 21 || vector == 29 || vector == 30",
+    "    .if error_code == 0",
+    "        push $0",
+    "    .endif",
+    "    push $vector",
+    "    jmp interrupt_common",
+    "    .set vector, vector + 1",
+    ".endr",
+    "",
+    "interrupt_common:",
+    "    pushal",
+    "    cld",
+    "    push %esp",
+    "    call dispatch_interrupt",
+    "    add $4, %esp",
+    "    popal",
+    "    add $8, %esp",
+    "    iretl",
+    stub_size = const STUB_SIZE,
+    vector_count = const VECTOR_COUNT,
+    options(att_syntax)
+);
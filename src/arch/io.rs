@@ -1,6 +1,64 @@
 pub struct Port(pub u16);
 
+/// A value that can be transferred through an I/O port in a single `in` or `out` instruction. Only
+/// implemented for `u8`, `u16` and `u32`, the widths supported by the hardware.
+pub trait PortValue: sealed::Sealed + Sized {
+    fn read_from(port: &Port) -> Self;
+    fn write_to(self, port: &Port);
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+impl PortValue for u8 {
+    fn read_from(port: &Port) -> Self {
+        port.read_u8()
+    }
+
+    fn write_to(self, port: &Port) {
+        port.write_u8(self)
+    }
+}
+
+impl PortValue for u16 {
+    fn read_from(port: &Port) -> Self {
+        port.read_u16()
+    }
+
+    fn write_to(self, port: &Port) {
+        port.write_u16(self)
+    }
+}
+
+impl PortValue for u32 {
+    fn read_from(port: &Port) -> Self {
+        port.read_u32()
+    }
+
+    fn write_to(self, port: &Port) {
+        port.write_u32(self)
+    }
+}
+
+// Although the methods below have safe signatures, they interact directly with hardware. Reading or
+// writing the wrong port can have arbitrary side effects on the state of the machine.
 impl Port {
+    /// Reads a value of the given width from the port, e.g. `port.read::<u32>()`.
+    #[inline]
+    pub fn read<T: PortValue>(&self) -> T {
+        T::read_from(self)
+    }
+
+    /// Writes a value to the port using the instruction matching its width.
+    #[inline]
+    pub fn write<T: PortValue>(&self, value: T) {
+        value.write_to(self)
+    }
+
     pub fn write_u8(&self, value: u8) {
         unsafe {
             core::arch::asm!(
@@ -11,4 +69,75 @@ impl Port {
             );
         }
     }
+
+    /// Reads a byte from the port.
+    #[inline]
+    pub fn read_u8(&self) -> u8 {
+        let value: u8;
+        unsafe {
+            core::arch::asm!(
+                "inb %dx, %al",
+                out("al") value,
+                in("dx") self.0,
+                options(att_syntax)
+            );
+        }
+        value
+    }
+
+    /// Writes a 16-bit word to the port.
+    #[inline]
+    pub fn write_u16(&self, value: u16) {
+        unsafe {
+            core::arch::asm!(
+                "outw %ax, %dx",
+                in("ax") value,
+                in("dx") self.0,
+                options(att_syntax)
+            );
+        }
+    }
+
+    /// Reads a 16-bit word from the port.
+    #[inline]
+    pub fn read_u16(&self) -> u16 {
+        let value: u16;
+        unsafe {
+            core::arch::asm!(
+                "inw %dx, %ax",
+                out("ax") value,
+                in("dx") self.0,
+                options(att_syntax)
+            );
+        }
+        value
+    }
+
+    /// Writes a 32-bit double word to the port.
+    #[inline]
+    pub fn write_u32(&self, value: u32) {
+        unsafe {
+            core::arch::asm!(
+                "outl %eax, %dx",
+                in("eax") value,
+                in("dx") self.0,
+                options(att_syntax)
+            );
+        }
+    }
+
+    /// Reads a 32-bit double word from the port.
+    #[inline]
+    pub fn read_u32(&self) -> u32 {
+        let value: u32;
+        unsafe {
+            core::arch::asm!(
+                "inl %dx, %eax",
+                out("eax") value,
+                in("dx") self.0,
+                options(att_syntax)
+            );
+        }
+        value
+    }
 }
@@ -0,0 +1,39 @@
+//! Accessors for memory-mapped device registers and buffers. All accesses are volatile, so the
+//! compiler neither elides nor merges them.
+
+/// A primitive value that can be read from or written to device memory in a single access.
+pub trait MmioValue: sealed::Sealed + Copy {}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+}
+
+impl MmioValue for u8 {}
+impl MmioValue for u16 {}
+impl MmioValue for u32 {}
+impl MmioValue for u64 {}
+
+/// Reads a value from device memory at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be mapped, suitably aligned for `T` and valid to read from, which for device
+/// registers may have side effects.
+#[inline]
+pub unsafe fn read_volatile<T: MmioValue>(addr: usize) -> T {
+    unsafe { (addr as *const T).read_volatile() }
+}
+
+/// Writes a value to device memory at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be mapped, suitably aligned for `T` and valid to write to.
+#[inline]
+pub unsafe fn write_volatile<T: MmioValue>(addr: usize, value: T) {
+    unsafe { (addr as *mut T).write_volatile(value) }
+}
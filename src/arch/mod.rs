@@ -1,8 +1,31 @@
 use core::arch::asm;
 
+#[cfg(target_arch = "x86")]
+pub mod apic;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod cpu;
+
+#[cfg(target_arch = "x86")]
+pub mod gdt;
+
+#[cfg(target_arch = "x86")]
+pub mod idt;
+
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub mod io;
 
+pub mod mmio;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod pci;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod serial;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod vga;
+
 /// Disable interrupts and stop execution on this core indefinitely.
 #[inline(always)]
 pub fn halt_core() -> ! {
@@ -12,6 +35,220 @@ pub fn halt_core() -> ! {
     }
 }
 
+/// Resets the machine. The 8042 keyboard controller is asked to pulse the CPU's reset line first.
+/// If that doesn't work (e.g. because there is no such controller), an empty IDT is loaded and an
+/// interrupt is raised. As the CPU can neither deliver it nor any of the resulting faults, it
+/// triple-faults, which resets the machine as well.
+pub fn reboot() -> ! {
+    const KEYBOARD_CONTROLLER: io::Port = io::Port(0x64);
+    const INPUT_BUFFER_FULL: u8 = 1 << 1;
+    const PULSE_RESET_LINE: u8 = 0xfe;
+
+    irq_disable();
+
+    // Wait until the controller can accept a command, but don't wait forever if there is none
+    for _ in 0..0x10000 {
+        if KEYBOARD_CONTROLLER.read_u8() & INPUT_BUFFER_FULL == 0 {
+            break;
+        }
+        pause();
+    }
+    KEYBOARD_CONTROLLER.write_u8(PULSE_RESET_LINE);
+    spin_delay(0x10_0000);
+
+    // The IDT descriptor consists of a 16-bit limit followed by the base address
+    let empty_idt = [0u16; 5];
+    unsafe {
+        asm!(
+            "lidt ({})",
+            "int3",
+            in(reg) &empty_idt,
+            options(att_syntax, noreturn)
+        );
+    }
+}
+
+/// Busy-waits for roughly `iterations` spin-loop iterations. This is meant for short delays during
+/// early device initialization before any timer has been configured, so the actual duration depends
+/// on the speed of the CPU. The loop cannot be optimized away because every iteration executes
+/// [`pause()`].
+#[inline]
+pub fn spin_delay(iterations: u32) {
+    for _ in 0..iterations {
+        pause();
+    }
+}
+
+/// Hints to the CPU that the caller is spinning in a busy-wait loop (e.g. for spinlock backoff).
+/// The `pause` instruction is encoded as `rep nop`, so it also works on CPUs that predate it.
+#[inline(always)]
+pub fn pause() {
+    unsafe {
+        asm!("pause", options(nomem, nostack));
+    }
+}
+
+/// The extended feature enable register. Its NXE bit (11) needs to be set before any mapping that
+/// has the no-execute bit set is installed, as the bit is reserved otherwise.
+#[allow(dead_code)]
+pub const IA32_EFER: u32 = 0xc000_0080;
+
+/// Reads the given model-specific register.
+// Although this function has a safe signature, reading a MSR that doesn't exist on this CPU raises
+// a general protection fault.
+#[inline]
+pub fn read_msr(msr: u32) -> u64 {
+    let (low, high): (u32, u32);
+    unsafe {
+        asm!(
+            "rdmsr",
+            in("ecx") msr,
+            out("eax") low,
+            out("edx") high,
+            options(att_syntax, nomem, nostack)
+        );
+    }
+    u64::from(high) << 32 | u64::from(low)
+}
+
+/// Writes the given model-specific register.
+// Although this function has a safe signature, writing a MSR can change the behavior of the CPU in
+// arbitrary ways and raises a general protection fault if the MSR doesn't exist.
+#[inline]
+#[allow(dead_code)]
+pub fn write_msr(msr: u32, value: u64) {
+    unsafe {
+        asm!(
+            "wrmsr",
+            in("ecx") msr,
+            in("eax") value as u32,
+            in("edx") (value >> 32) as u32,
+            options(att_syntax, nostack)
+        );
+    }
+}
+
+/// Invalidates the TLB entry of the page that contains the given virtual address. This must be
+/// done after changing or removing a mapping of the active address space.
+#[inline]
+#[allow(dead_code)]
+pub fn invlpg(addr: usize) {
+    unsafe {
+        asm!("invlpg ({})", in(reg) addr, options(att_syntax, nostack));
+    }
+}
+
+/// Invalidates all TLB entries except for those of global pages by reloading CR3.
+#[inline]
+#[allow(dead_code)]
+pub fn flush_tlb_all() {
+    unsafe { load_cr3(read_cr3()) };
+}
+
+/// CR0 bit that enables paging.
+#[allow(dead_code)]
+pub const CR0_PG: usize = 1 << 31;
+
+/// CR0 bit that makes read-only pages write-protected in kernel mode as well.
+#[allow(dead_code)]
+pub const CR0_WP: usize = 1 << 16;
+
+/// CR4 bit that enables 4 MiB pages (without PAE).
+#[allow(dead_code)]
+pub const CR4_PSE: usize = 1 << 4;
+
+/// CR4 bit that enables physical address extension.
+#[allow(dead_code)]
+pub const CR4_PAE: usize = 1 << 5;
+
+/// CR4 bit that enables global pages.
+#[allow(dead_code)]
+pub const CR4_PGE: usize = 1 << 7;
+
+/// Returns the value of CR0, which controls the operating mode of the CPU (e.g. paging).
+#[inline]
+#[allow(dead_code)]
+pub fn read_cr0() -> usize {
+    let value: usize;
+    unsafe {
+        asm!("mov %cr0, {}", out(reg) value, options(att_syntax, nomem, nostack));
+    }
+    value
+}
+
+/// Writes CR0, see [`read_cr0()`].
+///
+/// # Safety
+///
+/// Changing the operating mode of the CPU (e.g. enabling paging) must not pull the rug out from
+/// under the currently executing code.
+#[inline]
+#[allow(dead_code)]
+pub unsafe fn write_cr0(value: usize) {
+    unsafe {
+        asm!("mov {}, %cr0", in(reg) value, options(att_syntax, nostack));
+    }
+}
+
+/// Returns the linear address that caused the most recent page fault.
+#[inline]
+pub fn read_cr2() -> usize {
+    let value: usize;
+    unsafe {
+        asm!("mov %cr2, {}", out(reg) value, options(att_syntax, nomem, nostack));
+    }
+    value
+}
+
+/// Returns the value of CR4, which enables architectural extensions (e.g. 4 MiB pages).
+#[inline]
+#[allow(dead_code)]
+pub fn read_cr4() -> usize {
+    let value: usize;
+    unsafe {
+        asm!("mov %cr4, {}", out(reg) value, options(att_syntax, nomem, nostack));
+    }
+    value
+}
+
+/// Writes CR4, see [`read_cr4()`].
+///
+/// # Safety
+///
+/// Only extensions that the CPU supports may be enabled and changing the paging extensions must
+/// not invalidate the active address space.
+#[inline]
+#[allow(dead_code)]
+pub unsafe fn write_cr4(value: usize) {
+    unsafe {
+        asm!("mov {}, %cr4", in(reg) value, options(att_syntax, nostack));
+    }
+}
+
+/// Returns the physical address of the page directory of the active address space.
+#[inline]
+pub fn read_cr3() -> usize {
+    let value: usize;
+    unsafe {
+        asm!("mov %cr3, {}", out(reg) value, options(att_syntax, nomem, nostack));
+    }
+    value
+}
+
+/// Activates the address space whose page directory is located at the given physical address. This
+/// implicitly flushes all non-global TLB entries.
+///
+/// # Safety
+///
+/// The page directory must be page-aligned and map the currently executing code and stack at the
+/// same virtual addresses as the active address space.
+#[inline]
+pub unsafe fn load_cr3(page_directory: usize) {
+    unsafe {
+        asm!("mov {}, %cr3", in(reg) page_directory, options(att_syntax, nostack));
+    }
+}
+
 #[inline(always)]
 fn wait_for_irq() {
     unsafe {
@@ -19,9 +256,43 @@ fn wait_for_irq() {
     }
 }
 
+/// Disables interrupts on this core. Prefer [`irq_save()`] for critical sections, so that
+/// interrupts aren't enabled at the end of a critical section that was entered with interrupts
+/// disabled.
 #[inline(always)]
-fn irq_disable() {
+pub fn irq_disable() {
     unsafe {
         asm!("cli");
     }
 }
+
+/// Enables interrupts on this core.
+#[inline(always)]
+pub fn irq_enable() {
+    unsafe {
+        asm!("sti");
+    }
+}
+
+/// Disables interrupts on this core and returns whether they were enabled before. Pass the result
+/// to [`irq_restore()`] at the end of the critical section.
+#[inline]
+pub fn irq_save() -> bool {
+    const INTERRUPT_FLAG: usize = 1 << 9;
+
+    // Without `nomem`, the asm block is a compiler barrier, so no memory access of the critical
+    // section is moved before the `cli`
+    let flags: usize;
+    unsafe {
+        asm!("pushf", "pop {}", "cli", out(reg) flags, options(att_syntax));
+    }
+    flags & INTERRUPT_FLAG != 0
+}
+
+/// Enables interrupts again if they were enabled before the matching call to [`irq_save()`].
+#[inline]
+pub fn irq_restore(irq_enabled: bool) {
+    if irq_enabled {
+        irq_enable();
+    }
+}
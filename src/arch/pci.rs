@@ -0,0 +1,49 @@
+//! Access to the PCI configuration space using the legacy mechanism #1: The address of a register
+//! is written to port `0xcf8`, after which the register can be accessed through port `0xcfc`.
+
+use super::io::Port;
+use crate::sync::IrqSpinLock;
+
+/// The address and data ports. Selecting a register and accessing it are two separate port
+/// accesses, so they are done while holding the lock.
+static CONFIG_PORTS: IrqSpinLock<(Port, Port)> = IrqSpinLock::new((Port(0xcf8), Port(0xcfc)));
+
+/// Vendor ID read from the configuration space of devices that don't exist.
+const NO_DEVICE: u16 = 0xffff;
+
+/// Returns the value for the address port that selects the 32-bit register containing `offset`.
+fn config_address(bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+    debug_assert!(device < 32 && function < 8);
+    1 << 31
+        | (bus as u32) << 16
+        | (device as u32 & 0x1f) << 11
+        | (function as u32 & 0x7) << 8
+        | (offset as u32 & 0xfc)
+}
+
+/// Reads the 32-bit register at `offset` (rounded down to a multiple of 4) from the configuration
+/// space of a device function. Reads from absent devices return all ones.
+pub fn config_read_u32(bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+    let ports = CONFIG_PORTS.lock();
+    let (address, data) = &*ports;
+    address.write(config_address(bus, device, function, offset));
+    data.read()
+}
+
+/// Writes the 32-bit register at `offset` (rounded down to a multiple of 4) in the configuration
+/// space of a device function.
+#[allow(dead_code)]
+pub fn config_write_u32(bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+    let ports = CONFIG_PORTS.lock();
+    let (address, data) = &*ports;
+    address.write(config_address(bus, device, function, offset));
+    data.write(value);
+}
+
+/// Returns the vendor and device ID of a device function, or `None` if it doesn't exist.
+#[allow(dead_code)]
+pub fn read_vendor_device(bus: u8, device: u8, function: u8) -> Option<(u16, u16)> {
+    let ids = config_read_u32(bus, device, function, 0x00);
+    let vendor_id = ids as u16;
+    (vendor_id != NO_DEVICE).then_some((vendor_id, (ids >> 16) as u16))
+}
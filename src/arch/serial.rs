@@ -0,0 +1,99 @@
+//! Driver for the 16550 UART found behind the serial ports of virtually every PC. Unlike the QEMU
+//! debug console at port `0xe9`, it also produces output on real hardware.
+
+use super::io::Port;
+
+/// I/O port base of the first serial port (COM1).
+pub const COM1: u16 = 0x3f8;
+
+/// The UART's internal clock rate divided by 16. Dividing it by the baud rate yields the divisor.
+const BASE_BAUD_RATE: u32 = 115_200;
+
+/// The baud rate the UART is configured for.
+const BAUD_RATE: u32 = 115_200;
+
+/// Offsets of the UART registers relative to the base port.
+const DATA: u16 = 0;
+const INTERRUPT_ENABLE: u16 = 1;
+const FIFO_CONTROL: u16 = 2;
+const LINE_CONTROL: u16 = 3;
+const MODEM_CONTROL: u16 = 4;
+const LINE_STATUS: u16 = 5;
+
+/// Line status bit that indicates that the transmitter holding register can accept another byte.
+const TRANSMITTER_EMPTY: u8 = 1 << 5;
+
+/// A serial port driven by a 16550-compatible UART. Writing to it polls the line status register,
+/// so it works without interrupts and can be used as early as the kernel log.
+#[derive(Debug, Clone, Copy)]
+pub struct Serial {
+    base: u16,
+}
+
+impl Serial {
+    /// Creates a driver for the UART at the given I/O port base. The UART is not touched until
+    /// [`Serial::initialize()`] is called.
+    pub const fn new(base: u16) -> Self {
+        Self { base }
+    }
+
+    /// Returns the I/O port base of the UART.
+    pub fn base(&self) -> u16 {
+        self.base
+    }
+
+    /// Configures the UART for 115200 baud and 8N1 framing (8 data bits, no parity, 1 stop bit),
+    /// enables and clears its FIFOs and disables all of its interrupts.
+    pub fn initialize(&self) {
+        const DIVISOR_LATCH_ACCESS: u8 = 1 << 7;
+        const EIGHT_DATA_BITS: u8 = 0b11;
+        const FIFO_ENABLE_AND_CLEAR: u8 = 0b0000_0111;
+        const FIFO_TRIGGER_14_BYTES: u8 = 0b1100_0000;
+        const DATA_TERMINAL_READY: u8 = 1 << 0;
+        const REQUEST_TO_SEND: u8 = 1 << 1;
+        const OUT2: u8 = 1 << 3;
+
+        let divisor = (BASE_BAUD_RATE / BAUD_RATE) as u16;
+
+        self.register(INTERRUPT_ENABLE).write_u8(0);
+
+        // While the divisor latch is accessible, the first two registers hold the baud rate divisor
+        self.register(LINE_CONTROL).write_u8(DIVISOR_LATCH_ACCESS);
+        self.register(DATA).write_u8(divisor as u8);
+        self.register(INTERRUPT_ENABLE)
+            .write_u8((divisor >> 8) as u8);
+        self.register(LINE_CONTROL).write_u8(EIGHT_DATA_BITS);
+
+        self.register(FIFO_CONTROL)
+            .write_u8(FIFO_ENABLE_AND_CLEAR | FIFO_TRIGGER_14_BYTES);
+        self.register(MODEM_CONTROL)
+            .write_u8(DATA_TERMINAL_READY | REQUEST_TO_SEND | OUT2);
+    }
+
+    /// Waits until the UART is ready to accept another byte and then transmits it.
+    pub fn write_u8(&self, value: u8) {
+        while self.register(LINE_STATUS).read_u8() & TRANSMITTER_EMPTY == 0 {
+            super::pause();
+        }
+        self.register(DATA).write_u8(value);
+    }
+
+    fn register(&self, offset: u16) -> Port {
+        Port(self.base + offset)
+    }
+}
+
+impl Default for Serial {
+    fn default() -> Self {
+        Self::new(COM1)
+    }
+}
+
+impl core::fmt::Write for Serial {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.bytes() {
+            self.write_u8(c);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! Writer for the VGA text mode buffer. Most PC BIOSes leave the display in 80x25 text mode, in
+//! which case this can be used to show the kernel log on screen on machines without a serial port.
+
+/// Physical address of the VGA text buffer. Until paging is enabled, it is accessible directly.
+const BUFFER_ADDR: usize = 0xb8000;
+
+/// Number of character columns of the VGA text buffer.
+pub const COLUMNS: usize = 80;
+
+/// Number of character rows of the VGA text buffer.
+pub const ROWS: usize = 25;
+
+/// Attribute byte for light grey text on a black background.
+pub const LIGHT_GREY_ON_BLACK: u8 = 0x07;
+
+/// Writes text into the VGA text buffer, starting at a cursor position that is advanced with every
+/// character. Once the last row is full, the contents of the screen are scrolled up by one row.
+#[derive(Debug, Clone, Copy)]
+pub struct VgaText {
+    row: usize,
+    column: usize,
+    attribute: u8,
+}
+
+impl VgaText {
+    /// Creates a writer that starts at the given position with the given attribute byte (the upper
+    /// nibble selects the background and the lower nibble the foreground color). The position is
+    /// clamped to the 80x25 grid.
+    pub fn new(row: usize, column: usize, attribute: u8) -> Self {
+        Self {
+            row: row.min(ROWS - 1),
+            column: column.min(COLUMNS - 1),
+            attribute,
+        }
+    }
+
+    /// Returns the attribute byte characters are written with.
+    pub fn attribute(&self) -> u8 {
+        self.attribute
+    }
+
+    /// Changes the attribute byte subsequent characters are written with.
+    pub fn set_attribute(&mut self, attribute: u8) {
+        self.attribute = attribute;
+    }
+
+    /// Blanks the entire screen and moves the cursor to the top left corner.
+    pub fn clear(&mut self) {
+        for index in 0..ROWS * COLUMNS {
+            self.write_cell(index, b' ');
+        }
+        self.row = 0;
+        self.column = 0;
+    }
+
+    /// Writes a single character at the cursor position and advances the cursor. Bytes outside of
+    /// the printable ASCII range are shown as `?` as the VGA font uses code page 437.
+    pub fn write_u8(&mut self, c: u8) {
+        match c {
+            b'\n' => self.new_line(),
+            b'\r' => self.column = 0,
+            _ => {
+                let c = if c.is_ascii_graphic() || c == b' ' {
+                    c
+                } else {
+                    b'?'
+                };
+
+                self.write_cell(self.row * COLUMNS + self.column, c);
+                self.column += 1;
+                if self.column == COLUMNS {
+                    self.new_line();
+                }
+            }
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.column = 0;
+        if self.row + 1 < ROWS {
+            self.row += 1;
+        } else {
+            self.scroll();
+        }
+    }
+
+    /// Moves every row up by one and blanks the last one.
+    fn scroll(&mut self) {
+        for index in 0..(ROWS - 1) * COLUMNS {
+            // SAFETY: Both indices are within the bounds of the text buffer.
+            unsafe {
+                let cell = buffer().add(index + COLUMNS).read_volatile();
+                buffer().add(index).write_volatile(cell);
+            }
+        }
+        for index in (ROWS - 1) * COLUMNS..ROWS * COLUMNS {
+            self.write_cell(index, b' ');
+        }
+    }
+
+    fn write_cell(&self, index: usize, c: u8) {
+        debug_assert!(index < ROWS * COLUMNS);
+
+        // SAFETY: The index is within the bounds of the text buffer. Each cell consists of the
+        // character in its low byte and the attribute in its high byte.
+        unsafe {
+            buffer()
+                .add(index)
+                .write_volatile(u16::from(self.attribute) << 8 | u16::from(c));
+        }
+    }
+}
+
+impl Default for VgaText {
+    fn default() -> Self {
+        Self::new(0, 0, LIGHT_GREY_ON_BLACK)
+    }
+}
+
+impl core::fmt::Write for VgaText {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.bytes() {
+            self.write_u8(c);
+        }
+        Ok(())
+    }
+}
+
+fn buffer() -> *mut u16 {
+    BUFFER_ADDR as *mut u16
+}
@@ -0,0 +1,108 @@
+//! Stack backtraces for the panic handler
+//!
+//! The backtrace is obtained by walking the chain of saved frame pointers (`ebp`) on the stack, so
+//! it only works if the kernel is built without frame pointer omission. This is taken care of by
+//! the target configuration. The chain ends at the entry point, which clears `ebp` before calling
+//! into Rust code. Return addresses are resolved to the nearest function symbol using the kernel's
+//! ELF symbol table, if the bootloader passed it on.
+
+use crate::logging::write_panic;
+use crate::sync::SpinLock;
+
+/// Size of an entry of the ELF symbol table (`Elf32_Sym`) in bytes.
+pub const SYMBOL_SIZE: usize = 16;
+
+/// Upper bound for the number of frames printed, in case the frame pointer chain is corrupted.
+const MAX_DEPTH: usize = 32;
+
+/// The kernel's symbol table, if the bootloader passed it on.
+static SYMBOL_TABLE: SpinLock<Option<SymbolTable>> = SpinLock::new(None);
+
+/// The symbol table (`.symtab`) of the kernel's ELF image and the string table that contains the
+/// names of the symbols.
+pub struct SymbolTable {
+    symbols: &'static [u8],
+    strings: &'static [u8],
+}
+
+impl SymbolTable {
+    pub fn new(symbols: &'static [u8], strings: &'static [u8]) -> Self {
+        Self { symbols, strings }
+    }
+
+    /// Returns the name of the function that contains the given address and the offset of the
+    /// address within the function.
+    fn resolve(&self, addr: u32) -> Option<(&'static str, u32)> {
+        const STT_FUNC: u8 = 2;
+
+        let (name, value, _) = self
+            .symbols
+            .chunks_exact(SYMBOL_SIZE)
+            .filter(|symbol| symbol[12] & 0xf == STT_FUNC)
+            .map(|symbol| {
+                (
+                    read_u32(symbol, 0),
+                    read_u32(symbol, 4),
+                    read_u32(symbol, 8),
+                )
+            })
+            .find(|&(_, value, size)| (value..value.saturating_add(size)).contains(&addr))?;
+
+        let name = self.strings.get(name as usize..)?;
+        let name = core::ffi::CStr::from_bytes_until_nul(name).ok()?;
+        Some((name.to_str().ok()?, addr - value))
+    }
+}
+
+/// Makes the kernel's symbol table available for resolving return addresses.
+pub fn set_symbol_table(symbol_table: SymbolTable) {
+    *SYMBOL_TABLE.lock() = Some(symbol_table);
+}
+
+/// Prints the return addresses of all stack frames of the caller, resolved to function names if
+/// possible. Like the panic message, the backtrace bypasses the logger (see
+/// [`write_panic()`](crate::logging::write_panic)).
+#[inline(never)]
+pub fn log_backtrace() {
+    // The symbol table may be locked if a panic occurred while setting it
+    let symbol_table = SYMBOL_TABLE.try_lock();
+    let symbol_table = symbol_table.as_ref().and_then(|table| table.as_ref());
+
+    let mut frame_pointer: usize;
+    unsafe {
+        core::arch::asm!(
+            "mov %ebp, {}",
+            out(reg) frame_pointer,
+            options(att_syntax, nomem, nostack, preserves_flags)
+        );
+    }
+
+    write_panic(format_args!("Backtrace:"));
+    for depth in 0..MAX_DEPTH {
+        // Each frame starts with the caller's frame pointer followed by the return address
+        let frame = frame_pointer as *const usize;
+        if frame.is_null() || !frame.is_aligned() {
+            break;
+        }
+
+        let return_addr = unsafe { frame.add(1).read() };
+        if return_addr == 0 {
+            break;
+        }
+
+        match symbol_table.and_then(|table| table.resolve(return_addr as u32)) {
+            Some((name, offset)) => write_panic(format_args!(
+                "#{} 0x{:08x} {}+0x{:x}",
+                depth, return_addr, name, offset
+            )),
+            None => write_panic(format_args!("#{} 0x{:08x}", depth, return_addr)),
+        }
+
+        frame_pointer = unsafe { frame.read() };
+    }
+}
+
+/// Reads a little-endian `u32` at the given offset.
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
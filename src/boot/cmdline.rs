@@ -0,0 +1,56 @@
+//! Parser for the kernel command line passed on by the bootloader. The command line consists of
+//! arguments separated by whitespace, each of which is either a flag (`quiet`) or a key-value pair
+//! (`loglevel=debug`). Values containing whitespace can be enclosed in double quotes
+//! (`root="my disk"`).
+
+/// Splits the command line into its arguments and yields each one as its key and, if present, its
+/// value. Quotes around a value are removed.
+pub fn parse(command_line: &str) -> Arguments<'_> {
+    Arguments { rest: command_line }
+}
+
+/// Iterator over the arguments of a command line, returned by [`parse()`].
+#[derive(Debug, Clone)]
+pub struct Arguments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Arguments<'a> {
+    type Item = (&'a str, Option<&'a str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest.trim_start();
+        if rest.is_empty() {
+            self.rest = rest;
+            return None;
+        }
+
+        // The argument ends at the first whitespace character that isn't enclosed in quotes
+        let mut quoted = false;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                c.is_whitespace() && !quoted
+            })
+            .map_or(rest.len(), |(index, _)| index);
+
+        let (argument, rest) = rest.split_at(end);
+        self.rest = rest;
+
+        Some(match argument.split_once('=') {
+            Some((key, value)) => (key, Some(unquote(value))),
+            None => (argument, None),
+        })
+    }
+}
+
+/// Removes the double quotes around a value. A missing closing quote is tolerated.
+fn unquote(value: &str) -> &str {
+    match value.strip_prefix('"') {
+        Some(value) => value.strip_suffix('"').unwrap_or(value),
+        None => value,
+    }
+}
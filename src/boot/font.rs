@@ -0,0 +1,118 @@
+//! Bitmap font used to draw text on graphical framebuffers. It covers the printable ASCII
+//! characters, each of which is 8 pixels wide and 16 pixels high. The glyphs are those of the
+//! public domain `font8x8` font with every line doubled.
+
+/// Width of a glyph in pixels.
+pub const GLYPH_WIDTH: u32 = 8;
+
+/// Height of a glyph in pixels.
+pub const GLYPH_HEIGHT: u32 = 16;
+
+/// Returns the glyph of the given character or `None` if it isn't printable ASCII. Each byte of
+/// the glyph is one line from top to bottom and its most significant bit is the leftmost pixel.
+pub fn glyph(c: u8) -> Option<[u8; GLYPH_HEIGHT as usize]> {
+    let index = c.checked_sub(b' ')?;
+    GLYPHS
+        .get(usize::from(index))
+        .map(|glyph| glyph.to_be_bytes())
+}
+
+/// Glyphs of the characters from `' '` up to and including `'~'`. Each glyph is packed into an
+/// integer so that it fits on a single line.
+static GLYPHS: [u128; 95] = [
+    0x00000000_00000000_00000000_00000000, // space
+    0x18183c3c_3c3c1818_18180000_18180000, // `!`
+    0x6c6c6c6c_00000000_00000000_00000000, // `"`
+    0x6c6c6c6c_fefe6c6c_fefe6c6c_6c6c0000, // `#`
+    0x30307c7c_c0c07878_0c0cf8f8_30300000, // `$`
+    0x0000c6c6_cccc1818_30306666_c6c60000, // `%`
+    0x38386c6c_38387676_dcdccccc_76760000, // `&`
+    0x60606060_c0c00000_00000000_00000000, // `'`
+    0x18183030_60606060_60603030_18180000, // `(`
+    0x60603030_18181818_18183030_60600000, // `)`
+    0x00006666_3c3cffff_3c3c6666_00000000, // `*`
+    0x00003030_3030fcfc_30303030_00000000, // `+`
+    0x00000000_00000000_00003030_30306060, // `,`
+    0x00000000_0000fcfc_00000000_00000000, // `-`
+    0x00000000_00000000_00003030_30300000, // `.`
+    0x06060c0c_18183030_6060c0c0_80800000, // `/`
+    0x7c7cc6c6_cecedede_f6f6e6e6_7c7c0000, // `0`
+    0x30307070_30303030_30303030_fcfc0000, // `1`
+    0x7878cccc_0c0c3838_6060cccc_fcfc0000, // `2`
+    0x7878cccc_0c0c3838_0c0ccccc_78780000, // `3`
+    0x1c1c3c3c_6c6ccccc_fefe0c0c_1e1e0000, // `4`
+    0xfcfcc0c0_f8f80c0c_0c0ccccc_78780000, // `5`
+    0x38386060_c0c0f8f8_cccccccc_78780000, // `6`
+    0xfcfccccc_0c0c1818_30303030_30300000, // `7`
+    0x7878cccc_cccc7878_cccccccc_78780000, // `8`
+    0x7878cccc_cccc7c7c_0c0c1818_70700000, // `9`
+    0x00003030_30300000_00003030_30300000, // `:`
+    0x00003030_30300000_00003030_30306060, // `;`
+    0x18183030_6060c0c0_60603030_18180000, // `<`
+    0x00000000_fcfc0000_0000fcfc_00000000, // `=`
+    0x60603030_18180c0c_18183030_60600000, // `>`
+    0x7878cccc_0c0c1818_30300000_30300000, // `?`
+    0x7c7cc6c6_dededede_dedec0c0_78780000, // `@`
+    0x30307878_cccccccc_fcfccccc_cccc0000, // `A`
+    0xfcfc6666_66667c7c_66666666_fcfc0000, // `B`
+    0x3c3c6666_c0c0c0c0_c0c06666_3c3c0000, // `C`
+    0xf8f86c6c_66666666_66666c6c_f8f80000, // `D`
+    0xfefe6262_68687878_68686262_fefe0000, // `E`
+    0xfefe6262_68687878_68686060_f0f00000, // `F`
+    0x3c3c6666_c0c0c0c0_cece6666_3e3e0000, // `G`
+    0xcccccccc_ccccfcfc_cccccccc_cccc0000, // `H`
+    0x78783030_30303030_30303030_78780000, // `I`
+    0x1e1e0c0c_0c0c0c0c_cccccccc_78780000, // `J`
+    0xe6e66666_6c6c7878_6c6c6666_e6e60000, // `K`
+    0xf0f06060_60606060_62626666_fefe0000, // `L`
+    0xc6c6eeee_fefefefe_d6d6c6c6_c6c60000, // `M`
+    0xc6c6e6e6_f6f6dede_cecec6c6_c6c60000, // `N`
+    0x38386c6c_c6c6c6c6_c6c66c6c_38380000, // `O`
+    0xfcfc6666_66667c7c_60606060_f0f00000, // `P`
+    0x7878cccc_cccccccc_dcdc7878_1c1c0000, // `Q`
+    0xfcfc6666_66667c7c_6c6c6666_e6e60000, // `R`
+    0x7878cccc_e0e07070_1c1ccccc_78780000, // `S`
+    0xfcfcb4b4_30303030_30303030_78780000, // `T`
+    0xcccccccc_cccccccc_cccccccc_fcfc0000, // `U`
+    0xcccccccc_cccccccc_cccc7878_30300000, // `V`
+    0xc6c6c6c6_c6c6d6d6_fefeeeee_c6c60000, // `W`
+    0xc6c6c6c6_6c6c3838_38386c6c_c6c60000, // `X`
+    0xcccccccc_cccc7878_30303030_78780000, // `Y`
+    0xfefec6c6_8c8c1818_32326666_fefe0000, // `Z`
+    0x78786060_60606060_60606060_78780000, // `[`
+    0xc0c06060_30301818_0c0c0606_02020000, // `\`
+    0x78781818_18181818_18181818_78780000, // `]`
+    0x10103838_6c6cc6c6_00000000_00000000, // `^`
+    0x00000000_00000000_00000000_0000ffff, // `_`
+    0x30303030_18180000_00000000_00000000, // backtick
+    0x00000000_78780c0c_7c7ccccc_76760000, // `a`
+    0xe0e06060_60607c7c_66666666_dcdc0000, // `b`
+    0x00000000_7878cccc_c0c0cccc_78780000, // `c`
+    0x1c1c0c0c_0c0c7c7c_cccccccc_76760000, // `d`
+    0x00000000_7878cccc_fcfcc0c0_78780000, // `e`
+    0x38386c6c_6060f0f0_60606060_f0f00000, // `f`
+    0x00000000_7676cccc_cccc7c7c_0c0cf8f8, // `g`
+    0xe0e06060_6c6c7676_66666666_e6e60000, // `h`
+    0x30300000_70703030_30303030_78780000, // `i`
+    0x0c0c0000_0c0c0c0c_0c0ccccc_cccc7878, // `j`
+    0xe0e06060_66666c6c_78786c6c_e6e60000, // `k`
+    0x70703030_30303030_30303030_78780000, // `l`
+    0x00000000_ccccfefe_fefed6d6_c6c60000, // `m`
+    0x00000000_f8f8cccc_cccccccc_cccc0000, // `n`
+    0x00000000_7878cccc_cccccccc_78780000, // `o`
+    0x00000000_dcdc6666_66667c7c_6060f0f0, // `p`
+    0x00000000_7676cccc_cccc7c7c_0c0c1e1e, // `q`
+    0x00000000_dcdc7676_66666060_f0f00000, // `r`
+    0x00000000_7c7cc0c0_78780c0c_f8f80000, // `s`
+    0x10103030_7c7c3030_30303434_18180000, // `t`
+    0x00000000_cccccccc_cccccccc_76760000, // `u`
+    0x00000000_cccccccc_cccc7878_30300000, // `v`
+    0x00000000_c6c6d6d6_fefefefe_6c6c0000, // `w`
+    0x00000000_c6c66c6c_38386c6c_c6c60000, // `x`
+    0x00000000_cccccccc_cccc7c7c_0c0cf8f8, // `y`
+    0x00000000_fcfc9898_30306464_fcfc0000, // `z`
+    0x1c1c3030_3030e0e0_30303030_1c1c0000, // `{`
+    0x18181818_18180000_18181818_18180000, // `|`
+    0xe0e03030_30301c1c_30303030_e0e00000, // `}`
+    0x7676dcdc_00000000_00000000_00000000, // `~`
+];
@@ -0,0 +1,336 @@
+//! Drawing on the graphical framebuffer set up by the bootloader. Until paging is enabled, the
+//! framebuffer is accessible directly at its physical address.
+
+// The framebuffer description is only available on `x86` (IA-32) through multiboot
+#![cfg(target_arch = "x86")]
+
+use super::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use super::multiboot::{FramebufferType, PixelFormat};
+use crate::arch::mmio;
+use core::fmt::{Display, Formatter};
+use core::mem::size_of;
+
+/// Describes a framebuffer independently of the boot protocol it was passed on by. Unlike the raw
+/// multiboot structure, it isn't packed, so its fields can be accessed directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FramebufferInfo {
+    /// The physical address of the framebuffer.
+    pub address: u64,
+
+    /// The number of bytes per line of the framebuffer, including any padding.
+    pub pitch: u32,
+
+    /// The width of the framebuffer in pixels (or characters in text mode).
+    pub width: u32,
+
+    /// The height of the framebuffer in pixels (or characters in text mode).
+    pub height: u32,
+
+    /// The number of bits per pixel.
+    pub bits_per_pixel: u8,
+
+    /// The type of the framebuffer or `None` if the bootloader passed an unknown type.
+    pub framebuffer_type: Option<FramebufferType>,
+
+    /// How the color of a pixel is encoded or `None` if the framebuffer has no pixels (text mode
+    /// and unknown types).
+    pub pixel_format: Option<PixelFormat>,
+}
+
+impl Display for FramebufferInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}x{}@{}, pitch={}, type={}",
+            self.width,
+            self.height,
+            self.bits_per_pixel,
+            self.pitch,
+            match self.framebuffer_type {
+                Some(FramebufferType::Indexed) => "indexed",
+                Some(FramebufferType::Rgb) => "rgb",
+                Some(FramebufferType::EgaText) => "text",
+                None => "unknown",
+            }
+        )
+    }
+}
+
+/// Draws on a direct color (RGB) framebuffer. Colors are given as `0xRRGGBB` and converted to the
+/// pixel format of the framebuffer. Everything outside of the framebuffer is clipped.
+pub struct FramebufferWriter {
+    framebuffer: FramebufferInfo,
+
+    /// Bit position and width of the red, green and blue components within a pixel.
+    components: [(u8, u8); 3],
+}
+
+impl FramebufferWriter {
+    /// Creates a writer for the given framebuffer. Returns `None` if it isn't a direct color
+    /// framebuffer, its pixels aren't a whole number of bytes wide or it isn't addressable.
+    pub fn new(framebuffer: FramebufferInfo) -> Option<Self> {
+        let Some(PixelFormat::Rgb {
+            red_shift,
+            red_mask_size,
+            green_shift,
+            green_mask_size,
+            blue_shift,
+            blue_mask_size,
+        }) = framebuffer.pixel_format
+        else {
+            return None;
+        };
+
+        let bits_per_pixel = framebuffer.bits_per_pixel;
+        if !matches!(bits_per_pixel, 8 | 16 | 24 | 32) {
+            return None;
+        }
+
+        // The entire framebuffer must be located below 4 GiB
+        let size = u64::from(framebuffer.pitch) * u64::from(framebuffer.height);
+        if framebuffer.address.checked_add(size)? > usize::MAX as u64 + 1 {
+            return None;
+        }
+
+        Some(Self {
+            framebuffer,
+            components: [
+                (red_shift, red_mask_size),
+                (green_shift, green_mask_size),
+                (blue_shift, blue_mask_size),
+            ],
+        })
+    }
+
+    /// Returns the width of the framebuffer in pixels.
+    fn width(&self) -> u32 {
+        self.framebuffer.width
+    }
+
+    /// Returns the height of the framebuffer in pixels.
+    fn height(&self) -> u32 {
+        self.framebuffer.height
+    }
+
+    /// Sets the pixel at the given position to the given color. Does nothing if the position is
+    /// outside of the framebuffer.
+    #[allow(dead_code)]
+    pub fn put_pixel(&mut self, x: u32, y: u32, rgb: u32) {
+        if x < self.width() && y < self.height() {
+            self.write_pixel(x, y, self.encode(rgb));
+        }
+    }
+
+    /// Fills the rectangle with the given top left corner and size with the given color. Parts of
+    /// the rectangle that lie outside of the framebuffer are skipped.
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, rgb: u32) {
+        let pixel = self.encode(rgb);
+        let x_end = x.saturating_add(width).min(self.width());
+        let y_end = y.saturating_add(height).min(self.height());
+        for y in y..y_end {
+            for x in x..x_end {
+                self.write_pixel(x, y, pixel);
+            }
+        }
+    }
+
+    /// Fills the entire framebuffer with the given color.
+    pub fn clear(&mut self, rgb: u32) {
+        self.fill_rect(0, 0, self.width(), self.height(), rgb);
+    }
+
+    /// Converts a `0xRRGGBB` color into a pixel value of the framebuffer's pixel format. Each
+    /// 8-bit component is truncated to the width of its field.
+    fn encode(&self, rgb: u32) -> u32 {
+        let [_, red, green, blue] = rgb.to_be_bytes();
+        [red, green, blue].into_iter().zip(self.components).fold(
+            0,
+            |pixel, (value, (shift, size))| {
+                let size = u32::from(size.min(8));
+                let value = u32::from(value) >> (8 - size);
+                pixel | value.checked_shl(u32::from(shift)).unwrap_or(0)
+            },
+        )
+    }
+
+    /// Copies the visible part of the line `source` to the line `destination`. Both lines must be
+    /// within the framebuffer. Reading video memory is slow, as it's uncached, so the line is
+    /// copied in aligned 32-bit words where possible.
+    fn copy_line(&mut self, source: u32, destination: u32) {
+        const WORD: usize = size_of::<u32>();
+
+        let pitch = self.framebuffer.pitch as usize;
+        let len = self.width() as usize * usize::from(self.framebuffer.bits_per_pixel / 8);
+        let base = self.framebuffer.address as usize;
+        let source = base + source as usize * pitch;
+        let destination = base + destination as usize * pitch;
+
+        // Words can only be used if both lines are equally aligned, which is the case unless the
+        // pitch isn't a multiple of the word size. Bytes before the first and after the last
+        // aligned word are copied individually.
+        let head = if source % WORD == destination % WORD {
+            (source.next_multiple_of(WORD) - source).min(len)
+        } else {
+            len
+        };
+        let tail = head + (len - head) / WORD * WORD;
+
+        // SAFETY: See `write_pixel()`. The word accesses are aligned as `source + head` and
+        // `destination + head` are both multiples of the word size.
+        unsafe {
+            for index in (0..head).chain(tail..len) {
+                let byte: u8 = mmio::read_volatile(source + index);
+                mmio::write_volatile(destination + index, byte);
+            }
+            for index in (head..tail).step_by(WORD) {
+                let word: u32 = mmio::read_volatile(source + index);
+                mmio::write_volatile(destination + index, word);
+            }
+        }
+    }
+
+    /// Stores a pixel value byte by byte, as pixels may be 3 bytes wide and thus unaligned. The
+    /// position must be within the framebuffer.
+    fn write_pixel(&mut self, x: u32, y: u32, pixel: u32) {
+        let bytes_per_pixel = usize::from(self.framebuffer.bits_per_pixel / 8);
+        let offset = y as usize * self.framebuffer.pitch as usize + x as usize * bytes_per_pixel;
+        let addr = self.framebuffer.address as usize + offset;
+
+        for (index, byte) in pixel.to_le_bytes()[..bytes_per_pixel].iter().enumerate() {
+            // SAFETY: The position has been checked against the framebuffer dimensions and `new()`
+            // made sure that the framebuffer is addressable. Volatile stores keep the compiler
+            // from eliding writes to memory that is never read.
+            unsafe { mmio::write_volatile(addr + index, *byte) };
+        }
+    }
+}
+
+/// A text console on a graphical framebuffer. It draws characters using the built-in 8x16
+/// [`font`] at a cursor position that is advanced with every character. Once the last row is full,
+/// the contents of the screen are scrolled up by one row.
+pub struct FramebufferConsole {
+    writer: FramebufferWriter,
+    rows: u32,
+    columns: u32,
+    row: u32,
+    column: u32,
+    foreground: u32,
+    background: u32,
+}
+
+impl FramebufferConsole {
+    /// Light grey, which matches the default text color of the VGA text mode.
+    pub const LIGHT_GREY: u32 = 0xaaaaaa;
+
+    /// Black, the background color of the console.
+    pub const BLACK: u32 = 0x000000;
+
+    /// Creates a console in the top left corner of the given framebuffer that draws light grey
+    /// text on a black background. Returns `None` if the framebuffer isn't supported by
+    /// [`FramebufferWriter`] or is too small to hold a single character.
+    pub fn new(framebuffer: FramebufferInfo) -> Option<Self> {
+        let writer = FramebufferWriter::new(framebuffer)?;
+        let rows = writer.height() / GLYPH_HEIGHT;
+        let columns = writer.width() / GLYPH_WIDTH;
+        if rows == 0 || columns == 0 {
+            return None;
+        }
+
+        Some(Self {
+            writer,
+            rows,
+            columns,
+            row: 0,
+            column: 0,
+            foreground: Self::LIGHT_GREY,
+            background: Self::BLACK,
+        })
+    }
+
+    /// Returns the color characters are drawn with as `0xRRGGBB`.
+    pub fn foreground(&self) -> u32 {
+        self.foreground
+    }
+
+    /// Changes the color subsequent characters are drawn with.
+    pub fn set_foreground(&mut self, rgb: u32) {
+        self.foreground = rgb;
+    }
+
+    /// Fills the entire screen with the background color and moves the cursor to the top left
+    /// corner.
+    pub fn clear(&mut self) {
+        self.writer.clear(self.background);
+        self.row = 0;
+        self.column = 0;
+    }
+
+    /// Draws a single character at the cursor position and advances the cursor. Bytes outside of
+    /// the printable ASCII range are shown as `?` as the font doesn't cover them.
+    pub fn write_u8(&mut self, c: u8) {
+        match c {
+            b'\n' => self.new_line(),
+            b'\r' => self.column = 0,
+            _ => {
+                let glyph = font::glyph(c)
+                    .or_else(|| font::glyph(b'?'))
+                    .unwrap_or_default();
+                self.draw_glyph(glyph);
+                self.column += 1;
+                if self.column == self.columns {
+                    self.new_line();
+                }
+            }
+        }
+    }
+
+    fn draw_glyph(&mut self, glyph: [u8; GLYPH_HEIGHT as usize]) {
+        let foreground = self.writer.encode(self.foreground);
+        let background = self.writer.encode(self.background);
+        let x = self.column * GLYPH_WIDTH;
+        let y = self.row * GLYPH_HEIGHT;
+
+        for (line, bits) in (y..).zip(glyph) {
+            for (pixel_x, bit) in (x..).zip((0..GLYPH_WIDTH).rev()) {
+                let pixel = if bits >> bit & 1 != 0 {
+                    foreground
+                } else {
+                    background
+                };
+                self.writer.write_pixel(pixel_x, line, pixel);
+            }
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.column = 0;
+        if self.row + 1 < self.rows {
+            self.row += 1;
+        } else {
+            self.scroll();
+        }
+    }
+
+    /// Moves every row of text up by one and blanks the last one.
+    fn scroll(&mut self) {
+        for line in GLYPH_HEIGHT..self.rows * GLYPH_HEIGHT {
+            self.writer.copy_line(line, line - GLYPH_HEIGHT);
+        }
+        self.writer.fill_rect(
+            0,
+            (self.rows - 1) * GLYPH_HEIGHT,
+            self.writer.width(),
+            GLYPH_HEIGHT,
+            self.background,
+        );
+    }
+}
+
+impl core::fmt::Write for FramebufferConsole {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.bytes() {
+            self.write_u8(c);
+        }
+        Ok(())
+    }
+}
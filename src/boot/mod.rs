@@ -3,10 +3,15 @@
 //! A dedicated bootloader (e.g. GRUB) must be used to load the kernel image into memory and pass
 //! control to it. The bootloader must also provide the kernel with information about the machine
 //! and its configuration (e.g. memory map, command line arguments, etc.). The modalities of these
-//! tasks are defined by the boot protocol. On the x86-32 architecture, this kernel uses the
-//! `multiboot` boot protocol. Please check the specification for details on how it works.
+//! tasks are defined by the boot protocol. On the x86-32 architecture, this kernel supports both
+//! the `multiboot` and the `multiboot2` boot protocols. Please check the specifications for details
+//! on how they work.
 
+pub mod cmdline;
+mod font;
+pub mod framebuffer;
 mod multiboot;
+mod multiboot2;
 
 /// Instance of the multiboot header in static memory. It is used to tell the bootloader which
 /// features the kernel requires from it. The header is placed in the `.multiboot` section of the
@@ -21,6 +26,26 @@ static MULTIBOOT_HEADER: multiboot::Header = multiboot::Header::new()
     .request_aligned_modules()
     .request_memory_map();
 
+#[cfg(target_arch = "x86")]
+const _: () = assert!(MULTIBOOT_HEADER.is_checksum_valid());
+
+/// Instance of the multiboot2 header in static memory. It allows bootloaders to load the kernel
+/// using the newer multiboot2 protocol as well. Just like the [`MULTIBOOT_HEADER`], it is placed in
+/// the `.multiboot` section as the specification requires it to be located in the first 32K of the
+/// binary.
+///
+/// More details: [multiboot2::Header]
+#[used]
+#[link_section = ".multiboot"]
+#[cfg(target_arch = "x86")]
+static MULTIBOOT2_HEADER: multiboot2::Header = multiboot2::Header::new();
+
+/// The kernel's own global descriptor table, which replaces the one left behind by the bootloader.
+///
+/// More details: [crate::arch::gdt::Gdt]
+#[cfg(target_arch = "x86")]
+static GDT: crate::arch::gdt::Gdt = crate::arch::gdt::Gdt::new();
+
 /// The top address of the boot stack. The stack grows downwards from this address.
 const BOOT_STACK_BASE: usize = 0x8_0000;
 
@@ -34,7 +59,8 @@ const BOOT_STACK_BASE: usize = 0x8_0000;
 /// Before jumping to the [`multiboot_main()`] function, this function will perform the following
 /// tasks:
 ///
-/// 1. Setup a stack by loading the `esp` register with the top address of the kernel stack.
+/// 1. Setup a stack by loading the `esp` register with the top address of the kernel stack and
+///    clear `ebp` to terminate the frame pointer chain used for backtraces.
 /// 2. Save the pointer to the multiboot information structure found in the `ebx` register.
 /// 3. Save the multiboot magic value found in the `eax` register.
 /// 4. Call the [`clear_bss()`] function.
@@ -50,6 +76,7 @@ unsafe extern "C" fn multiboot_start() {
     // * `esp`: Stack pointer is in an undefined state. We must load our own.
     core::arch::asm!(
         "mov ${stack_ptr}, %esp",
+        "xor %ebp, %ebp",
         "push %ebx",
         "push %eax",
         "call clear_bss",
@@ -60,41 +87,219 @@ unsafe extern "C" fn multiboot_start() {
 }
 
 /// Coming from [`multiboot_start()`], this is the first true Rust code that gets executed after
-/// the bootloader passes control to the kernel. Its tasks are:
-///
-/// 1. Initialize the kernel log.
-/// 2. Verify the multiboot magic value and information structure pointer.
-/// 3. Initialize the memory subsystem based on the memory map provided by the bootloader via the
-///    multiboot information structure.
+/// the bootloader passes control to the kernel. It installs the kernel's [`GDT`] and IDT,
+/// initializes the kernel log (mirrored to the first serial port) and then continues in
+/// [`boot_multiboot()`] or [`boot_multiboot2()`] depending on which version of the boot protocol
+/// the bootloader used. Both versions enter the kernel the same way, so they can only be told apart
+/// by the magic value.
 #[no_mangle]
 #[cfg(target_arch = "x86")]
-extern "C" fn multiboot_main(magic: u32, mb_ptr: *const multiboot::BootInfo) -> ! {
-    use log::{debug, info};
+extern "C" fn multiboot_main(magic: u32, mb_ptr: *const u8) -> ! {
+    use log::info;
 
-    crate::logging::initialize_kernel_log();
+    GDT.install();
+    crate::arch::idt::install();
+    crate::logging::initialize_kernel_log(None);
+    crate::logging::enable_serial_output(crate::arch::serial::Serial::default());
     info!("Kernel by Alexander Ulmer v{}", env!("CARGO_PKG_VERSION"));
     info!("Copyright 2017-2024");
 
+    if magic == multiboot2::BOOTLOADER_MAGIC {
+        boot_multiboot2(magic, mb_ptr.cast())
+    } else {
+        boot_multiboot(magic, mb_ptr.cast())
+    }
+}
+
+/// Continues the boot process using the information structure of the original multiboot protocol.
+/// Its tasks are:
+///
+/// 1. Verify the multiboot magic value and information structure pointer.
+/// 2. Apply the log level passed on the kernel command line (if any).
+/// 3. Show the kernel log on screen (in text mode or on the graphical framebuffer) and log the
+///    framebuffer and modules set up by the bootloader.
+/// 4. Verify that the machine has enough usable RAM to bootstrap the memory subsystem and halt with
+///    a clear message otherwise.
+/// 5. Initialize the memory subsystem based on the memory map provided by the bootloader via the
+///    multiboot information structure.
+#[cfg(target_arch = "x86")]
+fn boot_multiboot(magic: u32, mb_ptr: *const multiboot::BootInfo) -> ! {
     // Check multiboot magic value and try to dereference pointer to information structure
-    assert_eq!(magic, 0x2badb002, "Multiboot magic value mismatch");
-    let multiboot = unsafe {
-        mb_ptr
-            .as_ref()
-            .expect("Multiboot information structure pointer should be non-null")
-    };
+    let multiboot = unsafe { multiboot::BootInfo::try_from_addr(magic, mb_ptr) }
+        .unwrap_or_else(|error| halt_on_boot_info_error(error));
 
-    debug!("Multiboot structure @ {:?}", mb_ptr);
+    crate::logging::initialize_kernel_log(
+        multiboot
+            .command_line()
+            .and_then(|command_line| command_line.to_str().ok()),
+    );
+    let framebuffer = multiboot
+        .framebuffer()
+        .map(framebuffer::FramebufferInfo::from);
+    enable_screen_log(framebuffer);
+    log::debug!("Multiboot structure @ {:?}", mb_ptr);
+    if let Some(boot_device) = multiboot.boot_device() {
+        log::debug!("Boot device: {:?}", boot_device);
+    }
+
+    if let Some(symbol_table) = multiboot.symbol_table() {
+        crate::backtrace::set_symbol_table(symbol_table);
+    }
 
-    // Retrieve multiboot memory map and use it to bootstrap the memory subsystem
+    log_framebuffer(framebuffer);
+    for module in multiboot.modules().unwrap_or_default() {
+        log_module(module);
+    }
+
+    // Retrieve multiboot memory map and use it to bootstrap the memory subsystem. Fall back to the
+    // amounts of lower and upper memory if the bootloader doesn't provide a full memory map.
+    match multiboot.memory_map() {
+        Some(memory_map) => boot_kernel(memory_map),
+        None => boot_kernel(
+            multiboot
+                .basic_memory_map()
+                .expect("Expected multiboot memory map or memory info to be present"),
+        ),
+    }
+}
+
+/// Continues the boot process using the tagged information structure of the multiboot2 protocol.
+/// Its tasks are the same as those of [`boot_multiboot()`].
+#[cfg(target_arch = "x86")]
+fn boot_multiboot2(magic: u32, mb_ptr: *const multiboot2::BootInfo) -> ! {
+    // Check multiboot2 magic value and try to dereference pointer to information structure
+    let multiboot = unsafe { multiboot2::BootInfo::try_from_addr(magic, mb_ptr) }
+        .unwrap_or_else(|error| halt_on_boot_info_error(error));
+
+    crate::logging::initialize_kernel_log(
+        multiboot
+            .command_line()
+            .and_then(|command_line| command_line.to_str().ok()),
+    );
+    let framebuffer = multiboot
+        .framebuffer()
+        .map(framebuffer::FramebufferInfo::from);
+    enable_screen_log(framebuffer);
+    log::debug!("Multiboot2 structure @ {:?}", mb_ptr);
+
+    log_framebuffer(framebuffer);
+    for module in multiboot.modules() {
+        log_module(&module);
+    }
+
+    // Retrieve multiboot2 memory map and use it to bootstrap the memory subsystem
     let memory_map = multiboot
         .memory_map()
-        .expect("Expected multiboot memory map to be present");
+        .expect("Expected multiboot2 memory map to be present");
+    boot_kernel(memory_map)
+}
+
+/// The part of the boot process that no longer depends on the boot protocol: identify the CPU and
+/// bootstrap the memory subsystem based on the memory map provided by the bootloader.
+#[cfg(target_arch = "x86")]
+fn boot_kernel(memory_map: impl Iterator<Item = crate::mem::physical::MemoryChunk> + Clone) -> ! {
+    log_cpu();
+    check_minimum_memory(memory_map.clone());
     crate::mem::bootstrap_subsystem(memory_map);
 
     // TODO Implement the rest of the boot process here.
     crate::arch::halt_core();
 }
 
+/// Reports that the boot information passed on by the bootloader is unusable and halts.
+#[cfg(target_arch = "x86")]
+fn halt_on_boot_info_error(error: multiboot::MultibootError) -> ! {
+    log::error!(
+        "Cannot use boot information passed on by the bootloader: {}",
+        error
+    );
+    crate::arch::halt_core();
+}
+
+/// Refuses to boot on machines with too little RAM instead of failing somewhere deep inside the
+/// memory subsystem bootstrap. The bootstrap needs a usable chunk of temporary boot memory that can
+/// hold the kernel heap, see [`find_boot_memory()`](crate::mem::find_boot_memory).
+#[cfg(target_arch = "x86")]
+fn check_minimum_memory(
+    memory_map: impl Iterator<Item = crate::mem::physical::MemoryChunk> + Clone,
+) {
+    use crate::mem::{physical, ByteLength, BOOT_HEAP_SIZE};
+
+    if crate::mem::find_boot_memory(memory_map.clone()).is_none() {
+        log::error!(
+            "Insufficient RAM: need more than {} usable between 32 and 128 MiB, have {} in total",
+            BOOT_HEAP_SIZE.fmt_as_bytes(),
+            physical::total_usable_bytes(memory_map).fmt_as_bytes()
+        );
+        crate::arch::halt_core();
+    }
+}
+
+/// Shows the kernel log on screen. The display is in VGA text mode if the bootloader reports an
+/// EGA text framebuffer or doesn't report a framebuffer at all, in which case the display is still
+/// in the text mode set up by the BIOS. Otherwise, the log is drawn on the graphical framebuffer.
+#[cfg(target_arch = "x86")]
+fn enable_screen_log(framebuffer: Option<framebuffer::FramebufferInfo>) {
+    use multiboot::FramebufferType;
+
+    match framebuffer {
+        Some(framebuffer) if framebuffer.framebuffer_type != Some(FramebufferType::EgaText) => {
+            if let Some(console) = framebuffer::FramebufferConsole::new(framebuffer) {
+                crate::logging::enable_framebuffer_output(console);
+            }
+        }
+        _ => crate::logging::enable_vga_output(crate::arch::vga::VgaText::default()),
+    }
+}
+
+/// Prints the CPU vendor and the CPU features relevant to the kernel to the kernel log.
+#[cfg(target_arch = "x86")]
+fn log_cpu() {
+    use crate::arch::cpu::{self, Feature};
+
+    let Some(vendor) = cpu::vendor() else {
+        log::debug!("CPU does not support cpuid");
+        return;
+    };
+
+    log::debug!(
+        "CPU: {}",
+        core::str::from_utf8(&vendor).unwrap_or("unknown vendor")
+    );
+    for feature in Feature::ALL {
+        log::debug!(
+            "├─ {}: {}",
+            feature,
+            if cpu::has_feature(feature) {
+                "yes"
+            } else {
+                "no"
+            }
+        );
+    }
+}
+
+/// Prints the framebuffer set up by the bootloader (if any) to the kernel log.
+#[cfg(target_arch = "x86")]
+fn log_framebuffer(framebuffer: Option<framebuffer::FramebufferInfo>) {
+    if let Some(framebuffer) = framebuffer {
+        log::debug!("Framebuffer @ 0x{:x}: {}", framebuffer.address, framebuffer);
+    }
+}
+
+/// Prints a module loaded by the bootloader to the kernel log.
+#[cfg(target_arch = "x86")]
+fn log_module(module: &multiboot::Module) {
+    use crate::mem::ByteLength;
+
+    log::debug!(
+        "Module @ 0x{:x}: {} ({})",
+        module.start_addr(),
+        (module.len() as u64).fmt_as_bytes(),
+        module.string().unwrap_or_default()
+    );
+}
+
 /// Clears the entire BSS segment of the kernel image. This may corrupt kernel memory if the
 /// function is executed after data in the BSS segment has been mutated. Furthermore, this function
 /// assumes that the symbols `__bss_start` and `_bss_end` defined in the linker script are valid and
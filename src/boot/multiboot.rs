@@ -6,7 +6,12 @@
 // Multiboot is only specified for `x86` (IA-32) architecture
 #![cfg(target_arch = "x86")]
 
+use super::framebuffer::FramebufferInfo;
 use crate::mem::physical::{MemoryChunk, MemoryChunkClass};
+use core::fmt::{Display, Formatter};
+
+/// The magic value a multiboot-compliant bootloader passes on to the kernel in the `eax` register.
+pub const BOOTLOADER_MAGIC: u32 = 0x2bad_b002;
 
 /// The multiboot header must be present in the first 8KB of every multiboot-compliant kernel image.
 /// It is used to indicate to the bootloader which features and information the kernel requires.
@@ -15,6 +20,12 @@ pub struct Header {
     magic: u32,
     flags: u32,
     checksum: u32,
+
+    /// Only valid if bit 16 in the `flags` word is set. See [LoadAddressRequest] for details.
+    addresses: LoadAddressRequest,
+
+    /// Only valid if bit 2 in the `flags` word is set. See [GraphicsRequest] for details.
+    graphics: GraphicsRequest,
 }
 
 impl Header {
@@ -25,6 +36,13 @@ impl Header {
             magic: 0x1bad_b002,
             flags: 0,
             checksum: 0,
+            addresses: LoadAddressRequest::new(0, 0, 0, 0, 0),
+            graphics: GraphicsRequest {
+                mode_type: 0,
+                width: 0,
+                height: 0,
+                depth: 0,
+            },
         }
         .with_checksum()
     }
@@ -47,6 +65,67 @@ impl Header {
         .with_checksum()
     }
 
+    /// Requests that the bootloader sets up a linear graphics framebuffer with the given width and
+    /// height in pixels and the given number of bits per pixel. Any of them may be zero to indicate
+    /// that the kernel has no preference. The bootloader may choose a different mode if the
+    /// requested one is not available.
+    pub const fn request_framebuffer(self, width: u32, height: u32, depth: u32) -> Self {
+        Self {
+            flags: self.flags | (1 << 2),
+            graphics: GraphicsRequest {
+                mode_type: 0,
+                width,
+                height,
+                depth,
+            },
+            ..self
+        }
+        .with_checksum()
+    }
+
+    /// Requests that the bootloader sets up an EGA text mode with the given number of columns and
+    /// rows. Both may be zero to indicate that the kernel has no preference.
+    pub const fn request_textmode(self, columns: u32, rows: u32) -> Self {
+        Self {
+            flags: self.flags | (1 << 2),
+            graphics: GraphicsRequest {
+                mode_type: 1,
+                width: columns,
+                height: rows,
+                depth: 0,
+            },
+            ..self
+        }
+        .with_checksum()
+    }
+
+    /// Requests that the bootloader loads the kernel image at the given addresses instead of
+    /// interpreting it as an ELF file. This is required for kernel images in other formats (e.g.
+    /// flat binaries).
+    pub const fn request_load_addrs(self, addresses: LoadAddressRequest) -> Self {
+        Self {
+            flags: self.flags | (1 << 16),
+            addresses,
+            ..self
+        }
+        .with_checksum()
+    }
+
+    /// Returns the flags that tell the bootloader which features the kernel requires.
+    pub const fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns `true` if the header checksum is correct, i.e. the `magic`, `flags` and `checksum`
+    /// fields have a (wrapping) unsigned sum of zero. Bootloaders reject headers for which this
+    /// isn't the case.
+    pub const fn is_checksum_valid(&self) -> bool {
+        self.magic
+            .wrapping_add(self.flags)
+            .wrapping_add(self.checksum)
+            == 0
+    }
+
     /// Computes the header checksum which needs to be correct in order to form a valid multiboot
     /// header structure recognized by bootloaders. The `magic` and `flags` and `checksum` fields
     /// must have an unsigned sum of zero.
@@ -58,6 +137,91 @@ impl Header {
     }
 }
 
+// Every builder method must set its flag and keep the checksum valid, or bootloaders reject the
+// header. Checking this at compile time catches mistakes in the flag bits or the checksum.
+const _: () = {
+    assert!(Header::new().flags() == 0 && Header::new().is_checksum_valid());
+
+    let header = Header::new().request_aligned_modules();
+    assert!(header.flags() == 1 << 0 && header.is_checksum_valid());
+
+    let header = Header::new().request_memory_map();
+    assert!(header.flags() == 1 << 1 && header.is_checksum_valid());
+
+    let header = Header::new().request_framebuffer(1024, 768, 32);
+    assert!(header.flags() == 1 << 2 && header.is_checksum_valid());
+
+    let header = Header::new().request_textmode(80, 25);
+    assert!(header.flags() == 1 << 2 && header.is_checksum_valid());
+
+    let header = Header::new().request_load_addrs(LoadAddressRequest::new(0, 0, 0, 0, 0));
+    assert!(header.flags() == 1 << 16 && header.is_checksum_valid());
+};
+
+/// The address fields of the multiboot header. They tell the bootloader where to load the kernel
+/// image if it is not an ELF file. All addresses are physical addresses.
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+pub struct LoadAddressRequest {
+    /// The address of the beginning of the multiboot header. This is used to synchronize the
+    /// mapping between file offsets and physical memory addresses.
+    header_addr: u32,
+
+    /// The address of the beginning of the text segment. The file offset that corresponds to it
+    /// is derived from `header_addr`.
+    load_addr: u32,
+
+    /// The end address of the data segment. If zero, the bootloader assumes that the text and data
+    /// segments occupy the whole kernel image file.
+    load_end_addr: u32,
+
+    /// The end address of the BSS segment. The bootloader initializes this area to zero. If zero,
+    /// the bootloader assumes that there is no BSS segment.
+    bss_end_addr: u32,
+
+    /// The address to which the bootloader should jump to start the kernel.
+    entry_addr: u32,
+}
+
+impl LoadAddressRequest {
+    /// Constructs a new load address request. See the documentation of the individual fields for
+    /// the meaning of each address.
+    pub const fn new(
+        header_addr: u32,
+        load_addr: u32,
+        load_end_addr: u32,
+        bss_end_addr: u32,
+        entry_addr: u32,
+    ) -> Self {
+        Self {
+            header_addr,
+            load_addr,
+            load_end_addr,
+            bss_end_addr,
+            entry_addr,
+        }
+    }
+}
+
+/// The graphics fields of the multiboot header. They tell the bootloader which video mode the
+/// kernel prefers. Use [`Header::request_framebuffer()`] or [`Header::request_textmode()`] to set
+/// them.
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+struct GraphicsRequest {
+    /// 0 for a linear graphics mode, 1 for EGA text mode.
+    mode_type: u32,
+
+    /// The number of columns (pixels in graphics mode, characters in text mode).
+    width: u32,
+
+    /// The number of lines (pixels in graphics mode, characters in text mode).
+    height: u32,
+
+    /// The number of bits per pixel in graphics mode, zero in text mode.
+    depth: u32,
+}
+
 /// When the bootloader (e.g. GRUB) transfers control to the kernel, an instance of this struct is
 /// passed along to the kernel. It contains information vital to the kernel startup procedure.
 #[repr(C, packed)]
@@ -80,7 +244,7 @@ pub struct BootInfo {
     /// If bit 1 in the `flags` word is set, then the `boot_device` field is valid, and indicates
     /// which BIOS disk device the boot loader loaded the OS image from. The operating system may
     /// use this field as a hint for determining its own root device, but is not required to.
-    _boot_device: u32,
+    boot_device: u32,
 
     /// If bit 2 of the `flags` word is set, the `cmdline` field is valid, and contains the
     /// physical address of the command line to be passed to the kernel. The command line is a
@@ -97,9 +261,23 @@ pub struct BootInfo {
     /// were loaded along with the kernel image, and where they can be found. `mods_addr` contains
     /// the physical address of the first module structure. For details each module's structure see
     /// the [Module] structure.
-    mods_addr: *const _Module,
+    mods_addr: *const Module,
 
-    _unused: [u32; 4],
+    /// If bit 5 of `flags` is set, then the `elf_*` fields describe the section header table of
+    /// the ELF kernel image. `elf_num` contains the number of entries in the table.
+    elf_num: u32,
+
+    /// If bit 5 of `flags` is set, `elf_size` contains the size of each section header table
+    /// entry in bytes.
+    elf_size: u32,
+
+    /// If bit 5 of `flags` is set, `elf_addr` contains the physical address of the section
+    /// header table. For details on each entry's structure see [`ElfSectionHeader`].
+    elf_addr: *const u8,
+
+    /// If bit 5 of `flags` is set, `elf_shndx` contains the index of the section that holds the
+    /// string table of the section names.
+    _elf_shndx: u32,
 
     /// If bit 6 in the `flags` word is set, then the `mmap_length` field is valid and indicates
     /// the address and length of a buffer containing a memory map of the machine provided by the
@@ -113,12 +291,43 @@ pub struct BootInfo {
     /// map provided is guaranteed to list all standard RAM that should be available for normal
     /// use.
     mmap: *const u8,
-    // ...
+
+    /// Fields describing the BIOS drives, the ROM configuration table, the bootloader name, the
+    /// APM table and the VBE interface. The kernel doesn't use any of them at the moment.
+    _unused: [u32; 9],
+
+    /// If bit 12 in the `flags` word is set, then the `framebuffer` fields are valid and describe
+    /// the framebuffer set up by the bootloader. For details see [Framebuffer].
+    framebuffer: Framebuffer,
 }
 
 impl BootInfo {
+    /// Validates the magic value and the information structure pointer passed on to the kernel by
+    /// the bootloader and returns a reference to the information structure if both are valid.
+    ///
+    /// # Safety
+    ///
+    /// If `ptr` passes validation, it must point to a multiboot information structure that stays
+    /// valid and unmodified for the rest of the kernel's lifetime.
+    pub unsafe fn try_from_addr(
+        magic: u32,
+        ptr: *const BootInfo,
+    ) -> Result<&'static BootInfo, MultibootError> {
+        if magic != BOOTLOADER_MAGIC {
+            Err(MultibootError::BadMagic(magic))
+        } else if ptr.is_null() {
+            Err(MultibootError::Null)
+        } else if !ptr.cast::<u32>().is_aligned() {
+            Err(MultibootError::Misaligned)
+        } else {
+            // SAFETY: We just checked that the pointer is non-null and aligned. The caller
+            // guarantees that it points to a valid information structure.
+            Ok(unsafe { &*ptr })
+        }
+    }
+
     /// Returns the kernel command line if one has been passed along by the bootloader.
-    pub fn _command_line(&self) -> Option<&core::ffi::CStr> {
+    pub fn command_line(&self) -> Option<&core::ffi::CStr> {
         const COMMAND_LINE_PRESENT: u32 = 1 << 2;
         if self.flags & COMMAND_LINE_PRESENT != 0 && !self.cmdline.is_null() {
             Some(unsafe { core::ffi::CStr::from_ptr(self.cmdline) })
@@ -128,7 +337,7 @@ impl BootInfo {
     }
 
     /// If present, returns a slice of modules passed on to the kernel by the bootloader.
-    pub fn _modules(&self) -> Option<&[_Module]> {
+    pub fn modules(&self) -> Option<&[Module]> {
         const MODULES_PRESENT: u32 = 1 << 3;
         if self.flags & MODULES_PRESENT != 0 && !self.mods_addr.is_null() {
             Some(unsafe { core::slice::from_raw_parts(self.mods_addr, self.mods_count) })
@@ -137,6 +346,62 @@ impl BootInfo {
         }
     }
 
+    /// If present, returns an iterator over the section headers of the kernel's ELF image. This
+    /// can be used to locate sections like `.symtab` and `.strtab` at runtime.
+    pub fn elf_sections<'mb>(
+        &'mb self,
+    ) -> Option<impl Iterator<Item = &'mb ElfSectionHeader> + Clone + 'mb> {
+        const ELF_SECTIONS_PRESENT: u32 = 1 << 5;
+        if self.flags & ELF_SECTIONS_PRESENT != 0 && !self.elf_addr.is_null() {
+            let (table, entry_size) = (self.elf_addr, self.elf_size as usize);
+            Some((0..self.elf_num as usize).map(move |index| {
+                // SAFETY: We just checked that the section header table is present and the
+                // pointer to it is non-null. The bootloader guarantees that it contains `elf_num`
+                // entries of `elf_size` bytes each.
+                unsafe { &*table.add(index * entry_size).cast::<ElfSectionHeader>() }
+            }))
+        } else {
+            None
+        }
+    }
+
+    /// Locates the kernel's symbol table (`.symtab`) and the string table containing the names of
+    /// the symbols using the section header table. Returns `None` if either of them is missing.
+    pub fn symbol_table(&self) -> Option<crate::backtrace::SymbolTable> {
+        const SHT_SYMTAB: u32 = 2;
+
+        let mut sections = self.elf_sections()?;
+        let symbols = sections
+            .clone()
+            .find(|section| section.r#type == SHT_SYMTAB)?;
+        let strings = sections.nth(symbols.link as usize)?;
+        if symbols.addr == 0
+            || strings.addr == 0
+            || symbols.entry_size as usize != crate::backtrace::SYMBOL_SIZE
+        {
+            return None;
+        }
+
+        // SAFETY: The bootloader copies both sections into memory and sets their addresses. They
+        // live as long as the information structure.
+        unsafe {
+            Some(crate::backtrace::SymbolTable::new(
+                core::slice::from_raw_parts(symbols.addr as *const u8, symbols.size as usize),
+                core::slice::from_raw_parts(strings.addr as *const u8, strings.size as usize),
+            ))
+        }
+    }
+
+    /// Returns information about the framebuffer set up by the bootloader if there is a valid one.
+    pub fn framebuffer(&self) -> Option<Framebuffer> {
+        const FRAMEBUFFER_PRESENT: u32 = 1 << 12;
+        if self.flags & FRAMEBUFFER_PRESENT != 0 {
+            Some(self.framebuffer).filter(Framebuffer::is_valid)
+        } else {
+            None
+        }
+    }
+
     /// This function returns an iterator that can be used to traverse the memory map passed on to
     /// the kernel by the bootloader or `None` if there is no memory map present.
     pub fn memory_map<'mb>(&'mb self) -> Option<impl Iterator<Item = MemoryChunk> + Clone + 'mb> {
@@ -154,11 +419,83 @@ impl BootInfo {
             None
         }
     }
+
+    /// Same as [`BootInfo::memory_map()`], but the returned iterator only yields the chunks of
+    /// memory that are available for use by the kernel.
+    #[allow(dead_code)]
+    pub fn available_memory<'mb>(
+        &'mb self,
+    ) -> Option<impl Iterator<Item = MemoryChunk> + Clone + 'mb> {
+        self.memory_map()
+            .map(|memory_map| memory_map.filter(MemoryChunk::is_usable))
+    }
+
+    /// Returns the amount of lower memory (starting at address 0) in KiB, if present.
+    pub fn mem_lower_kib(&self) -> Option<u32> {
+        const MEMORY_INFO_PRESENT: u32 = 1 << 0;
+        (self.flags & MEMORY_INFO_PRESENT != 0).then_some(self.mem_lower)
+    }
+
+    /// Returns the amount of upper memory (starting at address 1 MiB) in KiB, if present. It only
+    /// extends up to the first memory hole, so it may underestimate the amount of RAM.
+    pub fn mem_upper_kib(&self) -> Option<u32> {
+        const MEMORY_INFO_PRESENT: u32 = 1 << 0;
+        (self.flags & MEMORY_INFO_PRESENT != 0).then_some(self.mem_upper)
+    }
+
+    /// Returns the BIOS disk device the bootloader loaded the kernel image from, if present.
+    pub fn boot_device(&self) -> Option<BootDevice> {
+        const BOOT_DEVICE_PRESENT: u32 = 1 << 1;
+        (self.flags & BOOT_DEVICE_PRESENT != 0).then(|| BootDevice::from(self.boot_device))
+    }
+
+    /// Constructs a memory map from the amounts of lower and upper memory. This is a fallback for
+    /// bootloaders that don't provide a full memory map via [`BootInfo::memory_map()`].
+    pub fn basic_memory_map(&self) -> Option<impl Iterator<Item = MemoryChunk> + Clone> {
+        const UPPER_MEMORY_START: u64 = 0x10_0000;
+
+        let lower = MemoryChunk {
+            base_addr: 0,
+            length: u64::from(self.mem_lower_kib()?) * 1024,
+            class: MemoryChunkClass::Available,
+        };
+        let upper = MemoryChunk {
+            base_addr: UPPER_MEMORY_START,
+            length: u64::from(self.mem_upper_kib()?) * 1024,
+            class: MemoryChunkClass::Available,
+        };
+        Some([lower, upper].into_iter())
+    }
+}
+
+/// Errors that can occur when validating the information passed on to the kernel by the bootloader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MultibootError {
+    /// The magic value doesn't match the one of the boot protocol (e.g. [`BOOTLOADER_MAGIC`]), so
+    /// the kernel was most likely not loaded by a compliant bootloader. Contains the magic value
+    /// that was found instead.
+    BadMagic(u32),
+
+    /// The pointer to the information structure is not aligned as required by the boot protocol.
+    Misaligned,
+
+    /// The pointer to the information structure is null.
+    Null,
+}
+
+impl Display for MultibootError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MultibootError::BadMagic(magic) => write!(f, "bad multiboot magic value 0x{:x}", magic),
+            MultibootError::Misaligned => f.write_str("misaligned information structure pointer"),
+            MultibootError::Null => f.write_str("null information structure pointer"),
+        }
+    }
 }
 
 /// An entry in the bootloader-provided module list.
 #[repr(C)]
-pub struct _Module {
+pub struct Module {
     /// Start address of the module.
     mod_start: u32,
 
@@ -173,6 +510,291 @@ pub struct _Module {
     _reserved: u32,
 }
 
+impl Module {
+    /// Constructs a module entry from its raw fields. This is used to represent modules passed on
+    /// by other boot protocols (e.g. multiboot2) in the same way.
+    ///
+    /// # Safety
+    ///
+    /// `string` must either be null or point to a zero-terminated string that stays valid for as
+    /// long as the module entry is used.
+    pub unsafe fn new(mod_start: u32, mod_end: u32, string: *const core::ffi::c_char) -> Self {
+        Self {
+            mod_start,
+            mod_end,
+            string,
+            _reserved: 0,
+        }
+    }
+
+    /// Returns the physical start address of the module.
+    pub fn start_addr(&self) -> usize {
+        self.mod_start as usize
+    }
+
+    /// Returns the physical end address of the module (exclusive).
+    pub fn end_addr(&self) -> usize {
+        self.mod_end as usize
+    }
+
+    /// Returns the size of the module in bytes.
+    pub fn len(&self) -> usize {
+        self.end_addr().saturating_sub(self.start_addr())
+    }
+
+    /// Returns the contents of the module. This dereferences the module's physical address, so it
+    /// must only be called while the module is identity-mapped (e.g. during early boot).
+    #[allow(dead_code)]
+    pub fn bytes(&self) -> &[u8] {
+        // SAFETY: The bootloader guarantees that the module occupies the memory between
+        // `mod_start` and `mod_end`. The caller is responsible for it being mapped.
+        unsafe { core::slice::from_raw_parts(self.start_addr() as *const u8, self.len()) }
+    }
+
+    /// Returns the string associated with the module (usually its command line) or `None` if there
+    /// is none or it isn't valid UTF-8.
+    pub fn string(&self) -> Option<&str> {
+        if self.string.is_null() {
+            return None;
+        }
+
+        // SAFETY: We just checked that the pointer is non-null and the bootloader guarantees that
+        // it points to a zero-terminated string.
+        unsafe { core::ffi::CStr::from_ptr(self.string) }
+            .to_str()
+            .ok()
+    }
+}
+
+/// An entry in the section header table of the kernel's ELF image (`Elf32_Shdr`), as passed on to
+/// the kernel by the bootloader.
+#[repr(C)]
+pub struct ElfSectionHeader {
+    /// Offset of the section name in the section name string table.
+    _name: u32,
+
+    /// The type of the section (e.g. 2 for `SHT_SYMTAB` and 3 for `SHT_STRTAB`).
+    r#type: u32,
+
+    /// Section attribute flags.
+    _flags: u32,
+
+    /// Address of the section in memory. Sections loaded by the bootloader have a valid physical
+    /// address here.
+    addr: u32,
+
+    /// Offset of the section in the ELF file.
+    _offset: u32,
+
+    /// Size of the section in bytes.
+    size: u32,
+
+    /// Index of an associated section (e.g. the string table of a symbol table).
+    link: u32,
+
+    /// Extra information whose meaning depends on the section type.
+    _info: u32,
+
+    /// Required alignment of the section.
+    _addr_align: u32,
+
+    /// Size of each entry if the section holds a table of fixed-size entries (e.g. symbols).
+    entry_size: u32,
+}
+
+/// The BIOS disk device (and the partition on it) the bootloader loaded the kernel image from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootDevice {
+    /// The BIOS drive number, e.g. `0x00` for the first floppy disk or `0x80` for the first hard
+    /// disk.
+    pub drive: u8,
+
+    /// The partition numbers from the top-level partition down to nested sub-partitions (e.g. BSD
+    /// partitions within a DOS partition), or `None` for levels that aren't used. Partition
+    /// numbers start at zero.
+    pub partitions: [Option<u8>; 3],
+}
+
+impl From<u32> for BootDevice {
+    /// Decodes the `boot_device` field, which consists of the drive number in its most significant
+    /// byte followed by the three partition levels. Unused partition levels are set to `0xff`.
+    fn from(boot_device: u32) -> Self {
+        let [drive, part1, part2, part3] = boot_device.to_be_bytes();
+        Self {
+            drive,
+            partitions: [part1, part2, part3].map(|part| (part != 0xff).then_some(part)),
+        }
+    }
+}
+
+/// Describes the framebuffer set up by the bootloader. Since this struct is packed, its fields may
+/// be unaligned and must only ever be copied, never referenced. The accessor methods take care of
+/// that.
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+pub struct Framebuffer {
+    /// The physical address of the framebuffer.
+    addr: u64,
+
+    /// The number of bytes per line of the framebuffer.
+    pitch: u32,
+
+    /// The width of the framebuffer in pixels (or characters in text mode).
+    width: u32,
+
+    /// The height of the framebuffer in pixels (or characters in text mode).
+    height: u32,
+
+    /// The number of bits per pixel.
+    bits_per_pixel: u8,
+
+    /// The type of the framebuffer. See [FramebufferType] for possible values.
+    framebuffer_type: u8,
+
+    /// Type-specific information about the color encoding of the framebuffer. See
+    /// [`PixelFormat`] for how it is decoded.
+    color_info: [u8; 6],
+}
+
+// The layout of the framebuffer fields is defined by the multiboot specification
+const _: () = assert!(core::mem::size_of::<Framebuffer>() == 28);
+
+impl Framebuffer {
+    /// Constructs a framebuffer description from its raw fields. This is used to represent
+    /// framebuffers passed on by other boot protocols (e.g. multiboot2) in the same way.
+    pub fn new(
+        addr: u64,
+        pitch: u32,
+        width: u32,
+        height: u32,
+        bits_per_pixel: u8,
+        framebuffer_type: u8,
+        color_info: [u8; 6],
+    ) -> Self {
+        Self {
+            addr,
+            pitch,
+            width,
+            height,
+            bits_per_pixel,
+            framebuffer_type,
+            color_info,
+        }
+    }
+
+    /// Checks that the framebuffer description is plausible. Some bootloaders report a framebuffer
+    /// but leave its fields zeroed, which would otherwise look like a valid 0x0 framebuffer at
+    /// address 0.
+    pub fn is_valid(&self) -> bool {
+        self.address() != 0 && self.pitch() != 0 && self.width() != 0 && self.height() != 0
+    }
+
+    /// Returns the physical address of the framebuffer.
+    pub fn address(&self) -> u64 {
+        self.addr
+    }
+
+    /// Returns the number of bytes per line of the framebuffer, including any padding.
+    pub fn pitch(&self) -> u32 {
+        self.pitch
+    }
+
+    /// Returns the width of the framebuffer in pixels (or characters in text mode).
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the framebuffer in pixels (or characters in text mode).
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of bits per pixel.
+    pub fn bits_per_pixel(&self) -> u8 {
+        self.bits_per_pixel
+    }
+
+    /// Returns the decoded type of the framebuffer or `None` if the bootloader passed an unknown
+    /// type.
+    pub fn framebuffer_type(&self) -> Option<FramebufferType> {
+        match self.framebuffer_type {
+            0 => Some(FramebufferType::Indexed),
+            1 => Some(FramebufferType::Rgb),
+            2 => Some(FramebufferType::EgaText),
+            _ => None,
+        }
+    }
+
+    /// Decodes the type-specific color information of the framebuffer. Returns `None` for text
+    /// mode and unknown framebuffer types, which don't have a pixel format.
+    pub fn pixel_format(&self) -> Option<PixelFormat> {
+        let info = self.color_info;
+        match self.framebuffer_type()? {
+            FramebufferType::Indexed => Some(PixelFormat::Indexed {
+                palette_addr: u32::from_le_bytes([info[0], info[1], info[2], info[3]]),
+                color_count: u16::from_le_bytes([info[4], info[5]]),
+            }),
+            FramebufferType::Rgb => Some(PixelFormat::Rgb {
+                red_shift: info[0],
+                red_mask_size: info[1],
+                green_shift: info[2],
+                green_mask_size: info[3],
+                blue_shift: info[4],
+                blue_mask_size: info[5],
+            }),
+            FramebufferType::EgaText => None,
+        }
+    }
+}
+
+impl From<Framebuffer> for FramebufferInfo {
+    fn from(framebuffer: Framebuffer) -> Self {
+        Self {
+            address: framebuffer.address(),
+            pitch: framebuffer.pitch(),
+            width: framebuffer.width(),
+            height: framebuffer.height(),
+            bits_per_pixel: framebuffer.bits_per_pixel(),
+            framebuffer_type: framebuffer.framebuffer_type(),
+            pixel_format: framebuffer.pixel_format(),
+        }
+    }
+}
+
+/// The type of a framebuffer set up by the bootloader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FramebufferType {
+    /// Each pixel is an index into a color palette.
+    Indexed,
+
+    /// Each pixel directly encodes its red, green and blue components.
+    Rgb,
+
+    /// The framebuffer is an EGA-compatible text buffer. Width and height are measured in
+    /// characters instead of pixels.
+    EgaText,
+}
+
+/// Describes how the color of a single pixel is encoded in a graphical framebuffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelFormat {
+    /// Each pixel is an index into a palette of `color_count` colors. The palette is located at
+    /// the physical address `palette_addr` and consists of 3-byte red, green and blue entries.
+    Indexed { palette_addr: u32, color_count: u16 },
+
+    /// Each pixel directly encodes its red, green and blue components. For each component, the
+    /// `*_shift` field contains the bit position of the component within the pixel and the
+    /// `*_mask_size` field contains its width in bits.
+    Rgb {
+        red_shift: u8,
+        red_mask_size: u8,
+        green_shift: u8,
+        green_mask_size: u8,
+        blue_shift: u8,
+        blue_mask_size: u8,
+    },
+}
+
 /// Provides an iterator over the multiboot memory map.
 #[derive(Clone)]
 struct MemoryMap<'mb> {
@@ -251,10 +873,19 @@ impl Into<MemoryChunk> for &MemoryMapEntry {
         MemoryChunk {
             base_addr: self.base_addr,
             length: self.length,
-            class: match self.r#type {
-                1 => MemoryChunkClass::Available,
-                _ => MemoryChunkClass::Unusable,
-            },
+            class: memory_chunk_class(self.r#type),
         }
     }
 }
+
+/// Maps the type of a memory map entry to the class of the corresponding [`MemoryChunk`]. The
+/// multiboot2 protocol uses the same type values, so this is shared between both versions.
+pub fn memory_chunk_class(r#type: u32) -> MemoryChunkClass {
+    match r#type {
+        1 => MemoryChunkClass::Available,
+        // Memory holding ACPI tables can be reused once the tables have been parsed.
+        3 => MemoryChunkClass::Reclaimable,
+        5 => MemoryChunkClass::Defective,
+        _ => MemoryChunkClass::Unusable,
+    }
+}
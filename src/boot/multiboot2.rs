@@ -0,0 +1,272 @@
+//! This module contains the structures used to implement the multiboot2 boot protocol as defined in
+//! the corresponding specification:
+//!
+//! https://www.gnu.org/software/grub/manual/multiboot2/multiboot.html (version 2.0)
+//!
+//! Unlike the original multiboot protocol, the information structure passed on by the bootloader is
+//! not a fixed struct but a list of tags, each of which carries one kind of information. Where
+//! possible, the information is converted to the types of the [multiboot](super::multiboot) module
+//! so that the rest of the boot process doesn't have to care which protocol was used.
+
+// Multiboot2 is only used on the `x86` (IA-32) architecture
+#![cfg(target_arch = "x86")]
+
+use super::multiboot::{self, Framebuffer, Module, MultibootError};
+use crate::mem::physical::MemoryChunk;
+use core::ffi::CStr;
+
+/// The magic value a multiboot2-compliant bootloader passes on to the kernel in the `eax` register.
+pub const BOOTLOADER_MAGIC: u32 = 0x36d7_6289;
+
+/// The multiboot2 header must be present in the first 32KB of every multiboot2-compliant kernel
+/// image and be aligned to 8 bytes. Its fixed fields are followed by a list of tags which tell the
+/// bootloader which features the kernel requires. Just like the multiboot header, this header
+/// requests that modules be aligned on page boundaries. The bootloader always provides a memory
+/// map if one is available, so it doesn't need to be requested.
+#[repr(C, align(8))]
+pub struct Header {
+    magic: u32,
+    architecture: u32,
+    header_length: u32,
+    checksum: u32,
+    module_alignment_tag: [u32; 2],
+    end_tag: [u32; 2],
+}
+
+impl Header {
+    /// Construct a valid multiboot2 header for the 32-bit protected mode of the i386 architecture.
+    pub const fn new() -> Self {
+        // Header tags consist of a 16-bit type, 16-bit flags and a 32-bit size.
+        const MODULE_ALIGNMENT_TAG: [u32; 2] = [6, 8];
+        const END_TAG: [u32; 2] = [0, 8];
+
+        Self {
+            magic: 0xe852_50d6,
+            architecture: 0,
+            header_length: core::mem::size_of::<Self>() as u32,
+            checksum: 0,
+            module_alignment_tag: MODULE_ALIGNMENT_TAG,
+            end_tag: END_TAG,
+        }
+        .with_checksum()
+    }
+
+    /// Computes the header checksum which needs to be correct in order to form a valid multiboot2
+    /// header recognized by bootloaders. The `magic`, `architecture`, `header_length` and
+    /// `checksum` fields must have an unsigned sum of zero.
+    const fn with_checksum(self) -> Self {
+        Self {
+            checksum: !(self.magic + self.architecture + self.header_length) + 1,
+            ..self
+        }
+    }
+}
+
+/// When a multiboot2-compliant bootloader transfers control to the kernel, a pointer to this
+/// struct is passed along. It is immediately followed by the tags that contain the actual
+/// information, each of them aligned to 8 bytes. The list is terminated by a tag of type 0.
+#[repr(C)]
+pub struct BootInfo {
+    /// The total size of the information structure including all tags in bytes.
+    total_size: u32,
+
+    /// Must be ignored by the OS.
+    _reserved: u32,
+}
+
+impl BootInfo {
+    /// Validates the magic value and the information structure pointer passed on to the kernel by
+    /// the bootloader and returns a reference to the information structure if both are valid.
+    ///
+    /// # Safety
+    ///
+    /// If `ptr` passes validation, it must point to a multiboot2 information structure that stays
+    /// valid and unmodified for the rest of the kernel's lifetime.
+    pub unsafe fn try_from_addr(
+        magic: u32,
+        ptr: *const BootInfo,
+    ) -> Result<&'static BootInfo, MultibootError> {
+        if magic != BOOTLOADER_MAGIC {
+            Err(MultibootError::BadMagic(magic))
+        } else if ptr.is_null() {
+            Err(MultibootError::Null)
+        } else if !ptr.cast::<u64>().is_aligned() {
+            Err(MultibootError::Misaligned)
+        } else {
+            // SAFETY: We just checked that the pointer is non-null and aligned. The caller
+            // guarantees that it points to a valid information structure.
+            Ok(unsafe { &*ptr })
+        }
+    }
+
+    /// Returns the kernel command line if one has been passed along by the bootloader.
+    pub fn command_line(&self) -> Option<&CStr> {
+        const COMMAND_LINE_TAG: u32 = 1;
+        CStr::from_bytes_until_nul(self.find_tag(COMMAND_LINE_TAG)?).ok()
+    }
+
+    /// Returns an iterator over the modules passed on to the kernel by the bootloader. Unlike the
+    /// original multiboot protocol, each module is described by a separate tag.
+    pub fn modules(&self) -> impl Iterator<Item = Module> + '_ {
+        const MODULE_TAG: u32 = 3;
+        self.tags()
+            .filter(|tag| tag.r#type == MODULE_TAG)
+            .map(|tag| {
+                // SAFETY: The module's string is stored inline right after its start and end
+                // address and is zero-terminated. It lives as long as the information structure.
+                unsafe {
+                    Module::new(
+                        read_u32(tag.data, 0),
+                        read_u32(tag.data, 4),
+                        tag.data[8..].as_ptr().cast(),
+                    )
+                }
+            })
+    }
+
+    /// Returns information about the framebuffer set up by the bootloader if there is a valid one.
+    pub fn framebuffer(&self) -> Option<Framebuffer> {
+        const FRAMEBUFFER_TAG: u32 = 8;
+        const INDEXED: u8 = 0;
+        const RGB: u8 = 1;
+
+        // The common part of the framebuffer tag is followed by two reserved bytes (GRUB deviates
+        // from the specification here, which only mentions a single byte) and the color info.
+        let data = self.find_tag(FRAMEBUFFER_TAG)?;
+        let common = data.get(..22)?;
+        let framebuffer_type = common[21];
+        let color_info = match framebuffer_type {
+            // Indexed framebuffers store their palette inline after the number of colors, so we
+            // pass on its address in the same way the original multiboot protocol does.
+            INDEXED => {
+                let num_colors = data.get(24..26)?;
+                let palette_size =
+                    3 * usize::from(u16::from_le_bytes([num_colors[0], num_colors[1]]));
+                let palette_addr = data.get(26..26 + palette_size)?.as_ptr() as usize as u32;
+                let mut color_info = [0; 6];
+                color_info[..4].copy_from_slice(&palette_addr.to_le_bytes());
+                color_info[4..].copy_from_slice(num_colors);
+                color_info
+            }
+            RGB => data.get(24..30)?.try_into().ok()?,
+            _ => [0; 6],
+        };
+
+        Some(Framebuffer::new(
+            read_u64(common, 0),
+            read_u32(common, 8),
+            read_u32(common, 12),
+            read_u32(common, 16),
+            common[20],
+            framebuffer_type,
+            color_info,
+        ))
+        .filter(Framebuffer::is_valid)
+    }
+
+    /// This function returns an iterator that can be used to traverse the memory map passed on to
+    /// the kernel by the bootloader or `None` if there is no memory map present.
+    pub fn memory_map(&self) -> Option<impl Iterator<Item = MemoryChunk> + Clone + '_> {
+        const MEMORY_MAP_TAG: u32 = 6;
+
+        // The memory map tag starts with the size and version of its entries. Each entry consists
+        // of a 64-bit base address, a 64-bit length and a 32-bit type.
+        let data = self.find_tag(MEMORY_MAP_TAG)?;
+        let entry_size = read_u32(data, 0) as usize;
+        if entry_size < 20 {
+            return None;
+        }
+
+        Some(data[8..].chunks_exact(entry_size).map(|entry| MemoryChunk {
+            base_addr: read_u64(entry, 0),
+            length: read_u64(entry, 8),
+            class: multiboot::memory_chunk_class(read_u32(entry, 16)),
+        }))
+    }
+
+    /// Same as [`BootInfo::memory_map()`], but the returned iterator only yields the chunks of
+    /// memory that are available for use by the kernel.
+    #[allow(dead_code)]
+    pub fn available_memory(&self) -> Option<impl Iterator<Item = MemoryChunk> + Clone + '_> {
+        self.memory_map()
+            .map(|memory_map| memory_map.filter(MemoryChunk::is_usable))
+    }
+
+    /// Returns the contents of the first tag of the given type, if present.
+    fn find_tag(&self, r#type: u32) -> Option<&[u8]> {
+        self.tags()
+            .find(|tag| tag.r#type == r#type)
+            .map(|tag| tag.data)
+    }
+
+    /// Returns an iterator over all tags of the information structure.
+    fn tags(&self) -> Tags<'_> {
+        // SAFETY: The bootloader guarantees that the information structure including all of its
+        // tags is `total_size` bytes long. The lifetime of the resulting reference is tied to the
+        // lifetime of the BootInfo struct.
+        let buffer = unsafe {
+            core::slice::from_raw_parts(
+                (self as *const Self).cast::<u8>(),
+                self.total_size as usize,
+            )
+        };
+
+        Tags {
+            buffer: buffer
+                .get(core::mem::size_of::<Self>()..)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A single tag of the multiboot2 information structure.
+struct Tag<'mb> {
+    /// The type of the tag, which determines how its contents are interpreted.
+    r#type: u32,
+
+    /// The contents of the tag without the type and size fields.
+    data: &'mb [u8],
+}
+
+/// Provides an iterator over the tags of the multiboot2 information structure.
+#[derive(Clone)]
+struct Tags<'mb> {
+    /// Reference to the buffer that contains all remaining tags.
+    buffer: &'mb [u8],
+}
+
+impl<'mb> Iterator for Tags<'mb> {
+    type Item = Tag<'mb>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        const END_TAG: u32 = 0;
+        const TAG_ALIGNMENT: usize = 8;
+
+        // Each tag starts with its type and its size in bytes (including the type and size
+        // fields). Tags are padded so that the next one is aligned to 8 bytes.
+        let r#type = read_u32(self.buffer.get(..8)?, 0);
+        let size = read_u32(self.buffer, 4) as usize;
+        if r#type == END_TAG {
+            return None;
+        }
+
+        let data = self.buffer.get(8..size)?;
+        self.buffer = self
+            .buffer
+            .get(size.next_multiple_of(TAG_ALIGNMENT)..)
+            .unwrap_or_default();
+        Some(Tag { r#type, data })
+    }
+}
+
+/// Reads a little-endian `u32` at the given offset. Fields within tags aren't necessarily aligned,
+/// so they are read byte-wise.
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Reads a little-endian `u64` at the given offset. Fields within tags aren't necessarily aligned,
+/// so they are read byte-wise.
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
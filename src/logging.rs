@@ -1,18 +1,96 @@
 //! Temporary implementation of kernel log
 
+use crate::arch::{serial::Serial, vga::VgaText};
+use crate::boot::{cmdline, framebuffer::FramebufferConsole};
+use crate::sync::IrqSpinLock;
 use core::fmt::Write;
-use log::{Metadata, Record};
+use core::sync::atomic::{AtomicU16, Ordering};
+use log::{Level, LevelFilter, Metadata, Record};
 
 /// Global instance of the kernel logger.
-static LOGGER: KernelLog = KernelLog {};
+static LOGGER: KernelLog = KernelLog {
+    serial_port: AtomicU16::new(0),
+    vga: IrqSpinLock::new(None),
+    framebuffer: IrqSpinLock::new(None),
+};
 
-pub fn initialize_kernel_log() {
-    log::set_logger(&LOGGER)
-        .map(|()| log::set_max_level(log::LevelFilter::Trace))
-        .unwrap();
+/// VGA attribute bytes used to highlight errors and warnings on screen.
+const VGA_LIGHT_RED_ON_BLACK: u8 = 0x0c;
+const VGA_YELLOW_ON_BLACK: u8 = 0x0e;
+
+/// Text colors used to highlight errors and warnings on a framebuffer console. They match the
+/// colors of the VGA text mode.
+const LIGHT_RED: u32 = 0xff5555;
+const YELLOW: u32 = 0xffff55;
+
+/// Whether errors and warnings written to the serial port are highlighted using ANSI escape codes.
+/// Terminal emulators (including QEMU's serial console) render them, but dumb terminals don't.
+const SERIAL_COLORS: bool = true;
+
+/// Maximum log level used unless a different one is passed on the kernel command line.
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Trace;
+
+/// Installs the kernel logger and sets the maximum log level. The level can be chosen by passing
+/// `loglevel=<level>` (`error`, `warn`, `info`, `debug` or `trace`) on the kernel command line.
+/// Otherwise, or if the level is unknown, it defaults to [`DEFAULT_LEVEL`]. As the command line is
+/// only known once the boot information has been validated, this function may be called again to
+/// apply it.
+pub fn initialize_kernel_log(command_line: Option<&str>) {
+    // The logger is already installed if this isn't the first call
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(
+        command_line
+            .and_then(parse_log_level)
+            .unwrap_or(DEFAULT_LEVEL),
+    );
 }
 
-struct KernelLog;
+/// Extracts the log level from the `loglevel=<level>` argument of the kernel command line.
+fn parse_log_level(command_line: &str) -> Option<LevelFilter> {
+    cmdline::parse(command_line)
+        .find_map(|(key, value)| value.filter(|_| key == "loglevel"))?
+        .parse()
+        .ok()
+}
+
+/// Initializes the given serial port and mirrors all subsequent log messages to it. Unlike the
+/// QEMU debug console, the serial port also works on real hardware.
+pub fn enable_serial_output(serial: Serial) {
+    serial.initialize();
+    LOGGER.serial_port.store(serial.base(), Ordering::Relaxed);
+}
+
+/// Stops mirroring log messages to the serial port. They are still written to the debug console.
+#[allow(dead_code)]
+pub fn disable_serial_output() {
+    LOGGER.serial_port.store(0, Ordering::Relaxed);
+}
+
+/// Clears the screen and mirrors all subsequent log messages to the VGA text buffer. This must only
+/// be enabled while the display is in text mode.
+pub fn enable_vga_output(mut vga: VgaText) {
+    vga.clear();
+    *LOGGER.vga.lock() = Some(vga);
+}
+
+/// Clears the screen and mirrors all subsequent log messages to the given framebuffer console. This
+/// is the only way to show the kernel log on screen if the bootloader set up a graphics mode.
+pub fn enable_framebuffer_output(mut console: FramebufferConsole) {
+    console.clear();
+    *LOGGER.framebuffer.lock() = Some(console);
+}
+
+struct KernelLog {
+    /// I/O port base of the serial port log messages are mirrored to, or zero if there is none.
+    serial_port: AtomicU16,
+
+    /// Writer for the VGA text buffer if log messages are written to the screen. It keeps track of
+    /// the cursor position, so it's protected by a lock.
+    vga: IrqSpinLock<Option<VgaText>>,
+
+    /// Console on the graphical framebuffer if log messages are drawn on it instead.
+    framebuffer: IrqSpinLock<Option<FramebufferConsole>>,
+}
 
 impl log::Log for KernelLog {
     fn enabled(&self, _: &Metadata) -> bool {
@@ -21,14 +99,105 @@ impl log::Log for KernelLog {
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
-            let mut writer = DebugWriter {};
-            writeln!(&mut writer, "{}", record.args()).unwrap();
+            write_record(&mut DebugWriter {}, record, false);
+
+            match self.serial_port.load(Ordering::Relaxed) {
+                0 => {}
+                base => write_record(&mut Serial::new(base), record, SERIAL_COLORS),
+            }
+
+            // Skip the screen if the lock is already held, e.g. because an exception occurred while
+            // a log message was being written to it
+            if let Some(mut vga) = self.vga.try_lock() {
+                if let Some(vga) = vga.as_mut() {
+                    // The VGA text buffer doesn't understand escape codes, so errors and warnings
+                    // are highlighted by writing them with a different attribute instead.
+                    let attribute = vga.attribute();
+                    vga.set_attribute(match record.level() {
+                        Level::Error => VGA_LIGHT_RED_ON_BLACK,
+                        Level::Warn => VGA_YELLOW_ON_BLACK,
+                        _ => attribute,
+                    });
+                    write_record(vga, record, false);
+                    vga.set_attribute(attribute);
+                }
+            }
+
+            if let Some(mut console) = self.framebuffer.try_lock() {
+                if let Some(console) = console.as_mut() {
+                    let foreground = console.foreground();
+                    console.set_foreground(match record.level() {
+                        Level::Error => LIGHT_RED,
+                        Level::Warn => YELLOW,
+                        _ => foreground,
+                    });
+                    write_record(console, record, false);
+                    console.set_foreground(foreground);
+                }
+            }
         }
     }
 
     fn flush(&self) {}
 }
 
+/// Writes a panic message directly to the outputs of the kernel log, bypassing the `log` crate.
+/// This works even if the logger hasn't been installed yet. None of the outputs can deadlock: the
+/// debug console and the serial port aren't protected by locks and the screen is skipped if its
+/// lock is already held (e.g. because the panic occurred while a log message was being written).
+pub fn write_panic(message: core::fmt::Arguments) {
+    // Errors are ignored, as there is nothing left to report them to
+    let _ = writeln!(DebugWriter {}, "{}", message);
+
+    match LOGGER.serial_port.load(Ordering::Relaxed) {
+        0 => {}
+        base => {
+            let (color, reset) = if SERIAL_COLORS {
+                ("\x1b[31m", "\x1b[0m")
+            } else {
+                ("", "")
+            };
+            let _ = writeln!(Serial::new(base), "{}{}{}", color, message, reset);
+        }
+    }
+
+    if let Some(mut vga) = LOGGER.vga.try_lock() {
+        if let Some(vga) = vga.as_mut() {
+            vga.set_attribute(VGA_LIGHT_RED_ON_BLACK);
+            let _ = writeln!(vga, "{}", message);
+        }
+    }
+
+    if let Some(mut console) = LOGGER.framebuffer.try_lock() {
+        if let Some(console) = console.as_mut() {
+            console.set_foreground(LIGHT_RED);
+            let _ = writeln!(console, "{}", message);
+        }
+    }
+}
+
+/// Writes a log record as a single line of the form `[LEVEL] target: message`. If `colors` is set,
+/// errors and warnings are highlighted using ANSI escape codes.
+fn write_record(writer: &mut impl Write, record: &Record, colors: bool) {
+    let color = match record.level() {
+        Level::Error if colors => "\x1b[31m",
+        Level::Warn if colors => "\x1b[33m",
+        _ => "",
+    };
+    let reset = if color.is_empty() { "" } else { "\x1b[0m" };
+
+    writeln!(
+        writer,
+        "{}[{:<5}] {}: {}{}",
+        color,
+        record.level(),
+        record.target(),
+        record.args(),
+        reset
+    )
+    .unwrap();
+}
+
 struct DebugWriter;
 
 impl core::fmt::Write for DebugWriter {
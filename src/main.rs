@@ -4,17 +4,29 @@
 #![feature(asm_const)] // stack pointer setup in boot::_multiboot_entry()
 #![feature(slice_from_ptr_range)] // mem::bss()
 
-// extern crate alloc;
+extern crate alloc;
 
 mod arch;
+mod backtrace;
 mod boot;
 mod logging;
 mod mem;
+mod sync;
 
 /// The panic handler is called whenever the kernel encountered an unrecoverable error. It's purpose
-/// is to halt the system and report debug information to the user.
+/// is to halt the system and report debug information (including a backtrace) to the user. If the
+/// kernel is built with the `reboot-on-panic` feature, the machine is reset instead of being
+/// halted.
 #[panic_handler]
 fn panic(reason: &core::panic::PanicInfo) -> ! {
-    log::error!("Halting due to unrecoverable kernel panic:\n{}", reason);
-    arch::halt_core();
+    logging::write_panic(format_args!(
+        "Halting due to unrecoverable kernel panic:\n{}",
+        reason
+    ));
+    backtrace::log_backtrace();
+    if cfg!(feature = "reboot-on-panic") {
+        arch::reboot();
+    } else {
+        arch::halt_core();
+    }
 }
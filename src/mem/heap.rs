@@ -0,0 +1,264 @@
+//! The kernel heap backs all allocations made through the `alloc` crate. It manages a single
+//! contiguous region of memory using a linked list of free blocks that is sorted by address, so
+//! that freed blocks can be merged with their neighbors.
+
+use crate::sync::IrqSpinLock;
+use core::alloc::{GlobalAlloc, Layout};
+use core::mem::{align_of, size_of};
+use core::ptr;
+
+/// The allocator used by the `alloc` crate. It must be given a region of memory using
+/// [`LinkedListHeap::init()`] before anything can be allocated.
+#[global_allocator]
+pub static HEAP: LinkedListHeap = LinkedListHeap::new();
+
+/// A first-fit allocator that keeps the free blocks of the heap in a linked list. Each free block
+/// stores its size and a pointer to the next free block in its first bytes, so no memory is needed
+/// for bookkeeping. Allocations are rounded up so that every block can hold this header once it's
+/// freed again.
+///
+/// The list is protected by an [`IrqSpinLock`], so interrupt handlers may allocate as well.
+pub struct LinkedListHeap {
+    free_list: IrqSpinLock<FreeList>,
+}
+
+/// The header stored at the start of every free block.
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+/// The first free block of the heap or null if there is none.
+struct FreeList {
+    head: *mut FreeBlock,
+}
+
+// SAFETY: The free blocks are only accessed while the lock of the heap is held.
+unsafe impl Send for FreeList {}
+
+impl LinkedListHeap {
+    pub const fn new() -> Self {
+        Self {
+            free_list: IrqSpinLock::new(FreeList {
+                head: ptr::null_mut(),
+            }),
+        }
+    }
+
+    /// Adds the memory region of `size` bytes at `start` to the heap. Parts of the region that are
+    /// too small or misaligned to hold a block are left unused.
+    ///
+    /// # Safety
+    ///
+    /// The region must be mapped, writeable and unused for the rest of the kernel's lifetime and
+    /// must not overlap with memory that has already been added to the heap.
+    pub unsafe fn init(&self, start: usize, size: usize) {
+        let Some(end) = start.checked_add(size) else {
+            return;
+        };
+        let start = start.next_multiple_of(BLOCK_ALIGN);
+        let end = end / BLOCK_ALIGN * BLOCK_ALIGN;
+        if start < end && end - start >= MIN_BLOCK_SIZE {
+            unsafe { self.free_list.lock().insert(start, end - start) };
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for LinkedListHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let (size, align) = block_layout(layout);
+        self.free_list
+            .lock()
+            .allocate(size, align)
+            .map_or(ptr::null_mut(), |addr| addr as *mut u8)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let (size, _) = block_layout(layout);
+        unsafe { self.free_list.lock().insert(ptr as usize, size) };
+    }
+
+    /// Resizes the block in place if possible, which is the case when shrinking it or when it's
+    /// followed by a free block that is large enough. Otherwise, the contents are moved to a new
+    /// block.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: The caller guarantees that the new size is valid for the alignment.
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        let (old_block_size, _) = block_layout(layout);
+        let (new_block_size, _) = block_layout(new_layout);
+
+        let resized = unsafe {
+            self.free_list
+                .lock()
+                .resize(ptr as usize, old_block_size, new_block_size)
+        };
+        if resized {
+            return ptr;
+        }
+
+        let new_ptr = unsafe { self.alloc(new_layout) };
+        if !new_ptr.is_null() {
+            unsafe {
+                ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                self.dealloc(ptr, layout);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Alignment of all blocks, so that each one can hold a [`FreeBlock`] header.
+const BLOCK_ALIGN: usize = align_of::<FreeBlock>();
+
+/// Size of the smallest block that can hold a [`FreeBlock`] header.
+const MIN_BLOCK_SIZE: usize = size_of::<FreeBlock>();
+
+/// Returns the size and alignment of the block used for an allocation with the given layout.
+fn block_layout(layout: Layout) -> (usize, usize) {
+    let size = layout
+        .size()
+        .max(MIN_BLOCK_SIZE)
+        .next_multiple_of(BLOCK_ALIGN);
+    (size, layout.align().max(BLOCK_ALIGN))
+}
+
+impl FreeList {
+    /// Takes `size` bytes aligned to `align` from the first free block that is large enough and
+    /// returns their address. The rest of the block remains on the list. Both parameters must be
+    /// multiples of [`BLOCK_ALIGN`].
+    fn allocate(&mut self, size: usize, align: usize) -> Option<usize> {
+        let mut link: *mut *mut FreeBlock = &mut self.head;
+
+        // SAFETY: All blocks on the list are valid free blocks that are owned by the heap.
+        unsafe {
+            while !(*link).is_null() {
+                let block = *link;
+                let start = block as usize;
+                let end = start + (*block).size;
+
+                if let Some(addr) = fit(start, end, size, align) {
+                    // Unlink the block and return the unused space before and after the allocation
+                    // to the list. It stays sorted, as both parts take the place of the block.
+                    let mut next = (*block).next;
+                    if addr + size < end {
+                        next = write_block(addr + size, end - addr - size, next);
+                    }
+                    if start < addr {
+                        next = write_block(start, addr - start, next);
+                    }
+                    *link = next;
+                    return Some(addr);
+                }
+
+                link = &mut (*block).next;
+            }
+        }
+        None
+    }
+
+    /// Puts the block of `size` bytes at `addr` on the list, keeping it sorted by address, and
+    /// merges it with its neighbors if they are adjacent.
+    ///
+    /// # Safety
+    ///
+    /// The block must not be in use, must not overlap with other free blocks and its address and
+    /// size must be multiples of [`BLOCK_ALIGN`] (with the size being at least [`MIN_BLOCK_SIZE`]).
+    unsafe fn insert(&mut self, addr: usize, size: usize) {
+        let mut previous: *mut FreeBlock = ptr::null_mut();
+        let mut next = self.head;
+
+        // SAFETY: All blocks on the list are valid free blocks that are owned by the heap.
+        unsafe {
+            while !next.is_null() && (next as usize) < addr {
+                previous = next;
+                next = (*next).next;
+            }
+
+            let block = if !next.is_null() && addr + size == next as usize {
+                write_block(addr, size + (*next).size, (*next).next)
+            } else {
+                write_block(addr, size, next)
+            };
+
+            if previous.is_null() {
+                self.head = block;
+            } else if previous as usize + (*previous).size == addr {
+                (*previous).size += (*block).size;
+                (*previous).next = (*block).next;
+            } else {
+                (*previous).next = block;
+            }
+        }
+    }
+
+    /// Changes the size of the allocated block at `addr` from `old_size` to `new_size` bytes
+    /// without moving it. Space that is no longer needed is put on the list and additional space
+    /// is taken from a free block that directly follows the allocated block. Returns `false` if
+    /// this isn't possible, in which case nothing is changed.
+    ///
+    /// # Safety
+    ///
+    /// The block must be in use and both sizes must have been computed using [`block_layout()`].
+    unsafe fn resize(&mut self, addr: usize, old_size: usize, new_size: usize) -> bool {
+        if new_size <= old_size {
+            // The space that is released must be able to hold a free block of its own
+            let rest = old_size - new_size;
+            if rest != 0 && rest < MIN_BLOCK_SIZE {
+                return false;
+            }
+            if rest != 0 {
+                unsafe { self.insert(addr + new_size, rest) };
+            }
+            return true;
+        }
+
+        let end = addr + old_size;
+        let extra = new_size - old_size;
+        let mut link: *mut *mut FreeBlock = &mut self.head;
+
+        // SAFETY: All blocks on the list are valid free blocks that are owned by the heap.
+        unsafe {
+            while !(*link).is_null() && (*link as usize) < end {
+                link = &mut (**link).next;
+            }
+
+            let block = *link;
+            if block as usize != end || (*block).size < extra {
+                return false;
+            }
+
+            let rest = (*block).size - extra;
+            if rest == 0 {
+                *link = (*block).next;
+            } else if rest >= MIN_BLOCK_SIZE {
+                *link = write_block(end + extra, rest, (*block).next);
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Returns the lowest address within `start..end` at which `size` bytes aligned to `align` fit,
+/// such that the space left over before and after them is either empty or can hold a free block.
+fn fit(start: usize, end: usize, size: usize, align: usize) -> Option<usize> {
+    let mut addr = start.next_multiple_of(align);
+    if addr != start && addr - start < MIN_BLOCK_SIZE {
+        addr = (start + MIN_BLOCK_SIZE).next_multiple_of(align);
+    }
+
+    let rest = end.checked_sub(addr.checked_add(size)?)?;
+    (rest == 0 || rest >= MIN_BLOCK_SIZE).then_some(addr)
+}
+
+/// Writes the header of a free block and returns a pointer to it.
+///
+/// # Safety
+///
+/// The memory at `addr` must be unused and able to hold a [`FreeBlock`].
+unsafe fn write_block(addr: usize, size: usize, next: *mut FreeBlock) -> *mut FreeBlock {
+    let block = addr as *mut FreeBlock;
+    unsafe { block.write(FreeBlock { size, next }) };
+    block
+}
@@ -1,106 +1,203 @@
 //! Memory management
 
 use core::fmt::{Display, Formatter, Result};
+use core::marker::PhantomData;
 
+pub mod heap;
 pub mod physical;
 
 /// Max size of physical memory direct mapping on 32-bit x86 (virtual address space size limit).
 #[cfg(target_arch = "x86")]
 pub const PHYS_MAP_LIMIT: u64 = 0x0800_0000; // 128 MiB
 
-pub fn bootstrap_subsystem(memory_map: impl Iterator<Item = physical::MemoryChunk> + Clone) {
-    // Print system memory map to the kernel log
-    print_memory_map(memory_map.clone());
-
-    // Find a usable memory range above 32 MiB (so it doesn't interfere with the kernel binary and
-    // modules) and below `PHYS_MAP_LIMIT`. This will be used temporarily to allocate pages
-    let tmp_allocator_memory = memory_map
+/// Lowest address of the temporary boot memory, so that it doesn't interfere with the kernel binary
+/// and modules.
+const BOOT_MEMORY_START: u64 = 0x0200_0000; // 32 MiB
+
+/// Size of the kernel heap that is set up in the temporary boot memory.
+pub const BOOT_HEAP_SIZE: u64 = 0x0010_0000; // 1 MiB
+
+/// Finds the chunk of memory used temporarily during the memory subsystem bootstrap: the last
+/// usable chunk between 32 MiB and [`PHYS_MAP_LIMIT`] that can hold the kernel heap and at least
+/// one page frame besides it. Returns `None` if the machine has no such chunk, in which case the
+/// kernel cannot boot.
+pub fn find_boot_memory(
+    memory_map: impl Iterator<Item = physical::MemoryChunk>,
+) -> Option<physical::MemoryChunk> {
+    memory_map
         .filter(|chunk| chunk.is_usable())
-        .filter_map(|chunk| chunk.crop(0x0200_0000, PHYS_MAP_LIMIT))
+        .filter_map(|chunk| chunk.crop(BOOT_MEMORY_START, PHYS_MAP_LIMIT))
+        .filter_map(|chunk| chunk.align_to_pages())
+        .filter(|chunk| chunk.length > BOOT_HEAP_SIZE)
         .last()
+}
+
+pub fn bootstrap_subsystem(memory_map: impl Iterator<Item = physical::MemoryChunk> + Clone) {
+    // Print system memory map to the kernel log. Sort and merge it first so that it's easier to
+    // read.
+    let mut buffer: [Option<physical::MemoryChunk>; 32] = Default::default();
+    print_memory_map(physical::coalesce(memory_map.clone(), &mut buffer));
+
+    // Find a usable memory range above 32 MiB and below `PHYS_MAP_LIMIT`. This will be used
+    // temporarily to allocate pages
+    let tmp_allocator_memory = find_boot_memory(memory_map)
         .expect("Cannot find a suitable chunk of temporary boot memory.");
 
     log::debug!("Boot memory: {}", tmp_allocator_memory);
 
+    // The beginning of the boot memory holds the kernel heap, the rest is handed out page by page.
+    // Paging is still disabled, so the heap can be accessed through its physical address.
+    let heap_start = tmp_allocator_memory.base_addr;
+    unsafe { heap::HEAP.init(heap_start as usize, BOOT_HEAP_SIZE as usize) };
+    log::debug!("Kernel heap: {}", BOOT_HEAP_SIZE.fmt_as_bytes());
+
+    let mut boot_mem = physical::BootMem::new(&tmp_allocator_memory);
+    boot_mem.reserve(heap_start..heap_start + BOOT_HEAP_SIZE);
+    log::debug!(
+        "Boot page frame allocator: {} pages",
+        boot_mem.remaining_frames()
+    );
+
     // TODO
-    // 1. Implement and initialise simple page frame allocator.
-    // 2. Implement boot page table mapper. If possible, use large pages.
+    // 1. Implement boot page table mapper. If possible, use large pages.
     //   a) ident map all available chunks up to 3GiB.
     //   b) direct map all available chunks up to 128MiB to 3.5 GiB.
     //   c) map kernel binary at just below 4GiB.
-    // 3. Implement the slab allocator.
-    // 4. Implement and setup the buddy allocators.
-    // 5. (Optional) Implement and setup the fast stack allocator.
-    // 6. Implement the kernel heap.
-    // 8. Move all data which needs to be kept into the kernel heap.
+    // 2. Implement the slab allocator.
+    // 3. Implement and setup the buddy allocators.
+    // 4. Setup the fast stack allocator for high memory.
+    // 5. Move the kernel heap out of the boot memory once it's mapped.
+    // 6. Move all data which needs to be kept into the kernel heap.
     // 7. Move kernel and its stack to the high half + rewind stack!
 }
 
 /// Prints the bootloader-provided memory map to the kernel log.
-fn print_memory_map(memory_map: impl Iterator<Item = physical::MemoryChunk>) {
+fn print_memory_map(memory_map: impl Iterator<Item = physical::MemoryChunk> + Clone) {
     log::info!("Bootloader-provided memory map:");
-
-    let total_bytes_available = memory_map
-        .map(|chunk| {
-            log::info!("├─ {}", chunk);
-            if chunk.is_usable() {
-                chunk.length
-            } else {
-                0
-            }
-        })
-        .sum::<u64>();
+    for chunk in memory_map.clone() {
+        log::info!("├─ {}", chunk);
+    }
 
     log::info!(
         "└─ total memory available: {}",
-        total_bytes_available.fmt_as_bytes()
+        physical::total_usable_bytes(memory_map).fmt_as_bytes()
     );
 }
 
 pub trait ByteLength {
-    fn in_gigabytes(&self) -> f32 {
-        self.in_megabytes() / 1024.0
-    }
-
-    fn in_megabytes(&self) -> f32 {
-        self.in_kilobytes() / 1024.0
-    }
-
-    fn in_kilobytes(&self) -> f32 {
-        self.in_bytes() as f32 / 1024.0
-    }
-
     fn in_bytes(&self) -> u64;
 
     fn fmt_as_bytes(self) -> ByteSizeFormatter<Self>
     where
         Self: Sized,
     {
-        ByteSizeFormatter(self)
+        ByteSizeFormatter(self, PhantomData)
+    }
+
+    #[allow(dead_code)]
+    fn fmt_as_decimal_bytes(self) -> ByteSizeFormatter<Self, Decimal>
+    where
+        Self: Sized,
+    {
+        ByteSizeFormatter(self, PhantomData)
     }
 }
 
-pub struct ByteSizeFormatter<T: ByteLength>(T);
+/// A system of units used by [`ByteSizeFormatter`] to print byte sizes.
+pub trait UnitSystem {
+    /// The factor between two consecutive units (e.g. between kilobytes and megabytes).
+    const BASE: u64;
 
-impl<T: ByteLength> Display for ByteSizeFormatter<T> {
+    /// The suffixes of the kilo-, mega- and giga-units in this unit system.
+    const SUFFIXES: [&'static str; 3];
+}
+
+/// Binary units based on powers of 1024 (KiB, MiB, GiB). Conventionally used for memory sizes.
+pub enum Binary {}
+
+impl UnitSystem for Binary {
+    const BASE: u64 = 1024;
+    const SUFFIXES: [&'static str; 3] = ["KiB", "MiB", "GiB"];
+}
+
+/// Decimal (SI) units based on powers of 1000 (kB, MB, GB). Conventionally used for disk sizes.
+pub enum Decimal {}
+
+impl UnitSystem for Decimal {
+    const BASE: u64 = 1000;
+    const SUFFIXES: [&'static str; 3] = ["kB", "MB", "GB"];
+}
+
+pub struct ByteSizeFormatter<T: ByteLength, U: UnitSystem = Binary>(T, PhantomData<U>);
+
+impl<T: ByteLength, U: UnitSystem> Display for ByteSizeFormatter<T, U> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        if self.0.in_bytes() >= 0x1_0000_0000 {
+        let bytes = self.0.in_bytes();
+        let [kilo, mega, giga] = [U::BASE, U::BASE.pow(2), U::BASE.pow(3)];
+        let [kilo_suffix, mega_suffix, giga_suffix] = U::SUFFIXES;
+
+        if bytes >= 4 * giga {
             // >= 4 GiB
-            write!(f, "{:.1} GiB", self.0.in_gigabytes())
-        } else if self.0.in_bytes() >= 0x0080_0000 {
+            write_in_unit(f, bytes, giga, giga_suffix)
+        } else if bytes >= 8 * mega {
             // >= 8 MiB
-            write!(f, "{:.1} MiB", self.0.in_megabytes())
-        } else if self.0.in_bytes() >= 0x2000 {
+            write_in_unit(f, bytes, mega, mega_suffix)
+        } else if bytes >= 8 * kilo {
             // >= 8 KiB
-            write!(f, "{:.1} KiB", self.0.in_kilobytes())
+            write_in_unit(f, bytes, kilo, kilo_suffix)
         } else {
-            write!(f, "{} B", self.0.in_bytes())
+            write!(f, "{} B", bytes)
         }
     }
 }
 
+/// Writes `bytes` in the given unit with one fractional digit, rounded to the nearest tenth (e.g.
+/// "1.5 MiB"). This only uses integer arithmetic, so no floating point code is needed.
+fn write_in_unit(f: &mut Formatter<'_>, bytes: u64, unit: u64, suffix: &str) -> Result {
+    let tenths = bytes.saturating_mul(10).saturating_add(unit / 2) / unit;
+    write!(f, "{}.{} {}", tenths / 10, tenths % 10, suffix)
+}
+
 impl ByteLength for u64 {
     fn in_bytes(&self) -> u64 {
         *self
     }
 }
+
+/// Formats a byte buffer as a canonical hex dump (like `hexdump -C`). Each line shows the offset
+/// of its first byte, up to 16 bytes in hexadecimal and the same bytes as ASCII, with `.` standing
+/// in for non-printable ones.
+#[allow(dead_code)]
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl Display for HexDump<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        const BYTES_PER_LINE: usize = 16;
+
+        for (line, bytes) in self.0.chunks(BYTES_PER_LINE).enumerate() {
+            write!(f, "{:08x} ", line * BYTES_PER_LINE)?;
+            for index in 0..BYTES_PER_LINE {
+                // An extra space separates the two halves of the line
+                if index % 8 == 0 {
+                    f.write_str(" ")?;
+                }
+                match bytes.get(index) {
+                    Some(byte) => write!(f, "{:02x} ", byte)?,
+                    None => f.write_str("   ")?,
+                }
+            }
+
+            f.write_str(" |")?;
+            for &byte in bytes {
+                let c = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f, "|")?;
+        }
+        Ok(())
+    }
+}
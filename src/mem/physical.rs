@@ -1,5 +1,6 @@
 use core::cmp::{max, min};
 use core::fmt::{Display, Formatter, Result};
+use core::ops::Range;
 
 struct _PhysicalMemory {
     /// Buddy allocator for contiguous ranges of physical page frames below 16 MiB. Used to
@@ -16,6 +17,181 @@ struct _PhysicalMemory {
     highmem_allocator: (),
 }
 
+/// Size of a page frame in bytes.
+pub const PAGE_SIZE: u64 = 4096;
+
+/// Allocates single page frames from a contiguous range of page frame numbers. Freed page frames
+/// are pushed onto a stack that is threaded through the freed frames themselves (each one stores
+/// the number of the next one in its first bytes), so no additional memory is needed to keep track
+/// of them. Allocations prefer page frames from this stack and only take fresh ones from the range
+/// once it's empty.
+///
+/// The free list is accessed through the physical addresses of the page frames, so this allocator
+/// can only be used as long as the page frames it manages are identity-mapped.
+pub struct StackPageFrameAllocator {
+    /// Page frames that have never been allocated.
+    range: Range<usize>,
+
+    /// Number of the most recently freed page frame, if any.
+    free_list: Option<usize>,
+}
+
+impl StackPageFrameAllocator {
+    /// Creates an allocator for the page frames in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range contains page frame 0. The free list is written through the physical
+    /// address of each freed page frame, which would be a null pointer for page frame 0.
+    #[allow(dead_code)]
+    pub fn new(range: Range<usize>) -> Self {
+        assert!(
+            range.is_empty() || range.start != 0,
+            "Page frame 0 cannot be managed by a StackPageFrameAllocator."
+        );
+        Self {
+            range,
+            free_list: None,
+        }
+    }
+
+    /// Returns the number of the page frames that are left in the range. Freed page frames are not
+    /// included.
+    #[allow(dead_code)]
+    pub fn remaining_pages(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Allocates a single page frame and returns its number, or `None` if there is none left.
+    #[allow(dead_code)]
+    pub fn alloc_page(&mut self) -> Option<usize> {
+        match self.free_list {
+            Some(page) => {
+                // SAFETY: The page frame has been freed, so it's identity-mapped and not in use.
+                // Its first bytes contain the number of the next free page frame.
+                let next = unsafe { ((page as u64 * PAGE_SIZE) as usize as *const usize).read() };
+                self.free_list = (next != usize::MAX).then_some(next);
+                Some(page)
+            }
+            None => self.range.next(),
+        }
+    }
+
+    /// Allocates a single page frame like [`Self::alloc_page()`] and passes its number to `zero`,
+    /// which must clear the page frame through whatever mapping the caller has set up for it. The
+    /// closure is invoked exactly once if the allocation succeeds and not at all otherwise.
+    #[allow(dead_code)]
+    pub fn alloc_zeroed_page(&mut self, zero: impl FnOnce(usize)) -> Option<usize> {
+        let page = self.alloc_page()?;
+        zero(page);
+        Some(page)
+    }
+
+    /// Returns a page frame to the allocator so that it can be handed out again.
+    ///
+    /// # Safety
+    ///
+    /// The page frame must have been allocated from this allocator, must no longer be in use and
+    /// must be identity-mapped as its first bytes are overwritten.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page` is page frame 0, which can never have been allocated from this allocator.
+    #[allow(dead_code)]
+    pub unsafe fn free_page(&mut self, page: usize) {
+        assert!(page != 0, "Cannot free page frame 0.");
+        let next = self.free_list.unwrap_or(usize::MAX);
+        unsafe { ((page as u64 * PAGE_SIZE) as usize as *mut usize).write(next) };
+        self.free_list = Some(page);
+    }
+}
+
+/// Maximum number of ranges that can be reserved in a [`BootMem`] allocator.
+const BOOT_MEM_RESERVED_RANGES: usize = 8;
+
+/// Allocates page frames from a single chunk of memory before paging is set up by moving a cursor
+/// from its start to its end. Frames are never freed. Ranges within the chunk (e.g. the kernel
+/// image) can be reserved so that the cursor skips the page frames overlapping them.
+pub struct BootMem {
+    /// Address of the next page frame to hand out.
+    next: u64,
+
+    /// End address of the last page frame that can be handed out.
+    end: u64,
+
+    /// Ranges above the cursor that must not be handed out.
+    reserved: [Option<Range<u64>>; BOOT_MEM_RESERVED_RANGES],
+}
+
+impl BootMem {
+    /// Creates an allocator for the page frames that lie entirely within `chunk`.
+    pub fn new(chunk: &MemoryChunk) -> Self {
+        Self {
+            next: chunk.base_addr.next_multiple_of(PAGE_SIZE),
+            end: chunk.end_addr() / PAGE_SIZE * PAGE_SIZE,
+            reserved: Default::default(),
+        }
+    }
+
+    /// Prevents the page frames overlapping `range` from being handed out. Page frames that have
+    /// already been allocated are not affected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than [`BOOT_MEM_RESERVED_RANGES`] ranges within the remaining memory need to
+    /// be remembered.
+    pub fn reserve(&mut self, range: Range<u64>) {
+        if range.is_empty() || range.end <= self.next || self.end <= range.start {
+            return;
+        }
+
+        // Ranges at either end of the remaining memory simply shrink it
+        if range.start <= self.next {
+            self.next = min(range.end.next_multiple_of(PAGE_SIZE), self.end);
+        } else if self.end <= range.end {
+            self.end = max(range.start / PAGE_SIZE * PAGE_SIZE, self.next);
+        } else {
+            let slot = self
+                .reserved
+                .iter_mut()
+                .find(|slot| slot.is_none())
+                .expect("Too many reserved ranges in boot memory.");
+            *slot = Some(range);
+        }
+    }
+
+    /// Allocates the next page frame that doesn't overlap with a reserved range and returns its
+    /// number, or `None` if the memory is exhausted.
+    #[allow(dead_code)]
+    pub fn alloc_frame(&mut self) -> Option<usize> {
+        loop {
+            let addr = self.next;
+            if self.end < addr + PAGE_SIZE {
+                return None;
+            }
+
+            let reserved = self
+                .reserved
+                .iter()
+                .flatten()
+                .find(|range| range.start < addr + PAGE_SIZE && addr < range.end);
+            match reserved {
+                Some(range) => self.next = range.end.next_multiple_of(PAGE_SIZE),
+                None => {
+                    self.next += PAGE_SIZE;
+                    return Some((addr / PAGE_SIZE) as usize);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of page frames between the cursor and the end of the memory, including
+    /// ones that are reserved.
+    pub fn remaining_frames(&self) -> usize {
+        (self.end.saturating_sub(self.next) / PAGE_SIZE) as usize
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MemoryChunk {
     pub base_addr: u64,
@@ -24,11 +200,14 @@ pub struct MemoryChunk {
 }
 
 impl MemoryChunk {
+    /// Removes all addresses below `min_addr` from this chunk. Returns `None` if no addresses
+    /// remain, so zero-length chunks are never returned.
     pub fn crop_start(self, min_addr: u64) -> Option<Self> {
-        if min_addr < self.end_addr() {
+        let base_addr = max(self.base_addr, min_addr);
+        if base_addr < self.end_addr() {
             Some(Self {
-                base_addr: min_addr,
-                length: self.end_addr() - max(self.base_addr, min_addr),
+                base_addr,
+                length: self.end_addr() - base_addr,
                 ..self
             })
         } else {
@@ -36,14 +215,13 @@ impl MemoryChunk {
         }
     }
 
+    /// Removes all addresses from `max_addr` upwards from this chunk. Returns `None` if no
+    /// addresses remain, so zero-length chunks are never returned.
     pub fn crop_end(self, max_addr: u64) -> Option<Self> {
-        if max_addr > self.base_addr {
+        let end_addr = min(self.end_addr(), max_addr);
+        if self.base_addr < end_addr {
             Some(MemoryChunk {
-                length: if max_addr < self.end_addr() {
-                    min(self.end_addr(), max_addr) - self.base_addr
-                } else {
-                    self.length
-                },
+                length: end_addr - self.base_addr,
                 ..self
             })
         } else {
@@ -51,11 +229,67 @@ impl MemoryChunk {
         }
     }
 
+    /// Removes all addresses outside of `min_addr..max_addr` from this chunk. Returns `None` if no
+    /// addresses remain.
     pub fn crop(self, min_addr: u64, max_addr: u64) -> Option<Self> {
         self.crop_start(min_addr)
             .and_then(|chunk| chunk.crop_end(max_addr))
     }
 
+    /// Returns the part of this chunk that lies within `range` (keeping its class), or `None` if
+    /// they have no address in common.
+    pub fn intersect(&self, range: Range<u64>) -> Option<Self> {
+        self.clone().crop(range.start, range.end)
+    }
+
+    /// Removes the addresses in `hole` from this chunk (e.g. to punch out memory occupied by the
+    /// kernel image). Depending on how `hole` overlaps with the chunk, this yields zero, one or two
+    /// remaining chunks of the same class.
+    pub fn subtract(&self, hole: Range<u64>) -> impl Iterator<Item = MemoryChunk> {
+        let (below, above) = if hole.is_empty() {
+            (Some(self.clone()), None)
+        } else {
+            (
+                self.clone().crop_end(hole.start),
+                self.clone().crop_start(hole.end),
+            )
+        };
+
+        below.into_iter().chain(above)
+    }
+
+    /// Rounds the base address up and the end address down to page boundaries so that only page
+    /// frames which lie entirely within this chunk remain. Returns `None` if there are none.
+    pub fn align_to_pages(self) -> Option<Self> {
+        let base_addr = self.base_addr.next_multiple_of(PAGE_SIZE);
+        let end_addr = self.end_addr() / PAGE_SIZE * PAGE_SIZE;
+        if base_addr < end_addr {
+            Some(Self {
+                base_addr,
+                length: end_addr - base_addr,
+                ..self
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the given address lies within this chunk.
+    pub fn contains(&self, addr: u64) -> bool {
+        (self.base_addr..self.end_addr()).contains(&addr)
+    }
+
+    /// Returns whether all addresses of the given range lie within this chunk. Empty ranges are
+    /// contained in every chunk.
+    pub fn contains_range(&self, range: Range<u64>) -> bool {
+        range.is_empty() || (self.base_addr <= range.start && range.end <= self.end_addr())
+    }
+
+    /// Returns whether this chunk and `other` have at least one address in common.
+    pub fn overlaps(&self, other: &MemoryChunk) -> bool {
+        self.base_addr < other.end_addr() && other.base_addr < self.end_addr()
+    }
+
     pub fn end_addr(&self) -> u64 {
         self.base_addr + self.length
     }
@@ -64,12 +298,25 @@ impl MemoryChunk {
         (self.base_addr / 4096) as usize
     }
 
+    /// Returns the number of the page that contains the last byte of this chunk.
     pub fn last_page(&self) -> usize {
-        ((self.base_addr + self.length) / 4096) as usize
+        (self.end_addr().saturating_sub(1) / 4096) as usize
     }
 
+    /// Returns the number of pages that are at least partially covered by this chunk.
     pub fn page_count(&self) -> usize {
-        self.last_page() - self.first_page() + 1
+        if self.length == 0 {
+            0
+        } else {
+            self.last_page() - self.first_page() + 1
+        }
+    }
+
+    /// Returns the numbers of the page frames that lie entirely within this chunk.
+    pub fn frames(&self) -> Range<usize> {
+        self.clone()
+            .align_to_pages()
+            .map_or(0..0, |chunk| chunk.first_page()..chunk.last_page() + 1)
     }
 
     pub fn is_usable(&self) -> bool {
@@ -90,11 +337,134 @@ impl core::fmt::Display for MemoryChunk {
     }
 }
 
+/// Sorts the chunks of a memory map by their base address and merges adjacent or overlapping chunks
+/// of the same class. Memory maps provided by the firmware are neither guaranteed to be sorted nor
+/// to be free of such chunks. As there is no heap to collect the memory map into, the caller has to
+/// provide a buffer for it. Any chunks that don't fit into the buffer are dropped.
+pub fn coalesce(
+    mut memory_map: impl Iterator<Item = MemoryChunk>,
+    buffer: &mut [Option<MemoryChunk>],
+) -> impl Iterator<Item = MemoryChunk> + Clone + '_ {
+    let mut count = 0;
+    for (slot, chunk) in buffer.iter_mut().zip(memory_map.by_ref()) {
+        *slot = Some(chunk);
+        count += 1;
+    }
+
+    if memory_map.next().is_some() {
+        log::warn!(
+            "Memory map exceeds {} entries, dropping the rest",
+            buffer.len()
+        );
+    }
+
+    let chunks = &mut buffer[..count];
+    chunks.sort_unstable_by_key(|chunk| chunk.as_ref().map(|chunk| chunk.base_addr));
+
+    // Either merge each chunk into the last one that has been kept or keep it as well
+    let mut kept: usize = 0;
+    for index in 0..chunks.len() {
+        let Some(chunk) = chunks[index].take() else {
+            continue;
+        };
+
+        match kept.checked_sub(1).and_then(|last| chunks[last].as_mut()) {
+            Some(last) if last.class == chunk.class && chunk.base_addr <= last.end_addr() => {
+                last.length = max(last.end_addr(), chunk.end_addr()) - last.base_addr;
+            }
+            _ => {
+                chunks[kept] = Some(chunk);
+                kept += 1;
+            }
+        }
+    }
+
+    buffer[..kept].iter().flatten().cloned()
+}
+
+/// Returns the total number of bytes of usable memory in the given memory map. Overlapping chunks
+/// are counted twice, so the memory map should be [coalesced](coalesce) first if it may contain
+/// any.
+pub fn total_usable_bytes(memory_map: impl Iterator<Item = MemoryChunk>) -> u64 {
+    memory_map
+        .filter(MemoryChunk::is_usable)
+        .map(|chunk| chunk.length)
+        .sum()
+}
+
+/// Returns the numbers of the page frames that lie entirely within the usable chunks of a memory
+/// map, one range per chunk. Partially covered page frames at the edges of a chunk are dropped, as
+/// they may share their memory with a reserved chunk.
+#[allow(dead_code)]
+pub fn usable_frame_ranges(
+    memory_map: impl Iterator<Item = MemoryChunk>,
+) -> impl Iterator<Item = Range<usize>> {
+    memory_map
+        .filter(MemoryChunk::is_usable)
+        .map(|chunk| chunk.frames())
+        .filter(|frames| !frames.is_empty())
+}
+
+/// Adapters that can be chained onto any iterator over the chunks of a memory map.
+pub trait MemoryMap: Iterator<Item = MemoryChunk> + Sized {
+    /// Crops the chunks to the addresses within `window`, keeping their class. Chunks outside of
+    /// it are dropped. Unlike mapping the memory map with a closure, the returned iterator can be
+    /// cloned whenever the memory map can.
+    #[allow(dead_code)]
+    fn clamp_to(self, window: Range<u64>) -> Clamp<Self> {
+        Clamp {
+            memory_map: self,
+            window,
+        }
+    }
+}
+
+impl<I: Iterator<Item = MemoryChunk>> MemoryMap for I {}
+
+/// Iterator over memory map chunks cropped to a window, returned by [`MemoryMap::clamp_to()`].
+#[derive(Debug, Clone)]
+pub struct Clamp<I> {
+    memory_map: I,
+    window: Range<u64>,
+}
+
+impl<I: Iterator<Item = MemoryChunk>> Iterator for Clamp<I> {
+    type Item = MemoryChunk;
+
+    fn next(&mut self) -> Option<MemoryChunk> {
+        let window = &self.window;
+        self.memory_map
+            .find_map(|chunk| chunk.intersect(window.clone()))
+    }
+}
+
+/// Splits the chunks of a memory map at the given (ascending) addresses in a single pass, e.g. to
+/// assign them to the ISA, PCI and high memory zones. Each resulting chunk is tagged with the index
+/// of the zone it falls into: zone `0` ends at `boundaries[0]`, zone `i` spans from
+/// `boundaries[i - 1]` to `boundaries[i]` and the last zone extends to the end of the address
+/// space. Chunks that straddle a boundary are cut in two.
+#[allow(dead_code)]
+pub fn split_at<'a>(
+    memory_map: impl Iterator<Item = MemoryChunk> + 'a,
+    boundaries: &'a [u64],
+) -> impl Iterator<Item = (usize, MemoryChunk)> + 'a {
+    memory_map.flat_map(move |chunk| {
+        (0..=boundaries.len()).filter_map(move |zone| {
+            let start = zone.checked_sub(1).map_or(0, |index| boundaries[index]);
+            let end = boundaries.get(zone).copied().unwrap_or(u64::MAX);
+            chunk.intersect(start..end).map(|chunk| (zone, chunk))
+        })
+    })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MemoryChunkClass {
     Available,
     Unusable,
     Reclaimable,
+
+    /// RAM that the firmware has found to be faulty.
+    Defective,
 }
 
 impl Display for MemoryChunkClass {
@@ -103,6 +473,7 @@ impl Display for MemoryChunkClass {
             MemoryChunkClass::Available => "usable",
             MemoryChunkClass::Unusable => "reserved",
             MemoryChunkClass::Reclaimable => "reclaimable",
+            MemoryChunkClass::Defective => "defective",
         })
     }
 }
@@ -0,0 +1,147 @@
+//! Synchronization primitives
+
+use core::cell::UnsafeCell;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A lock that busy-waits until it can be acquired. It must not be held while interrupts are
+/// enabled if the protected value is also accessed by an interrupt handler, as the handler would
+/// spin forever if it interrupted the holder of the lock. Use an [`IrqSpinLock`] in such cases.
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: The lock guarantees that only a single context accesses the value at a time.
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Spins until the lock can be acquired. The lock is released once the returned guard is
+    /// dropped.
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+
+            // Wait for the lock to be released without hammering it with atomic writes
+            while self.locked.load(Ordering::Relaxed) {
+                crate::arch::pause();
+            }
+        }
+    }
+
+    /// Acquires the lock if it's not held by anyone else.
+    pub fn try_lock(&self) -> Option<SpinLockGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinLockGuard { lock: self })
+    }
+}
+
+/// Provides access to the value protected by a [`SpinLock`] and releases it when dropped.
+pub struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The lock is held as long as the guard exists.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: The lock is held as long as the guard exists.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// A [`SpinLock`] that disables interrupts on the current core while it's held, so it can protect
+/// values that are shared with interrupt handlers. Once the lock is released, interrupts are
+/// enabled again only if they were enabled before.
+pub struct IrqSpinLock<T> {
+    lock: SpinLock<T>,
+}
+
+impl<T> IrqSpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            lock: SpinLock::new(value),
+        }
+    }
+
+    /// Disables interrupts and spins until the lock can be acquired.
+    pub fn lock(&self) -> IrqSpinLockGuard<'_, T> {
+        let irq_enabled = crate::arch::irq_save();
+        IrqSpinLockGuard {
+            guard: ManuallyDrop::new(self.lock.lock()),
+            irq_enabled,
+        }
+    }
+
+    /// Acquires the lock if it's not held by anyone else. Interrupts are left untouched if it
+    /// isn't.
+    pub fn try_lock(&self) -> Option<IrqSpinLockGuard<'_, T>> {
+        let irq_enabled = crate::arch::irq_save();
+        match self.lock.try_lock() {
+            Some(guard) => Some(IrqSpinLockGuard {
+                guard: ManuallyDrop::new(guard),
+                irq_enabled,
+            }),
+            None => {
+                crate::arch::irq_restore(irq_enabled);
+                None
+            }
+        }
+    }
+}
+
+/// Provides access to the value protected by an [`IrqSpinLock`]. When dropped, it releases the lock
+/// and restores the previous interrupt state.
+pub struct IrqSpinLockGuard<'a, T> {
+    guard: ManuallyDrop<SpinLockGuard<'a, T>>,
+    irq_enabled: bool,
+}
+
+impl<T> Deref for IrqSpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for IrqSpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for IrqSpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        // The lock must be released before interrupts are enabled again, otherwise an interrupt
+        // handler could try to acquire it in between and spin forever.
+        // SAFETY: The guard isn't used after this point.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        crate::arch::irq_restore(self.irq_enabled);
+    }
+}